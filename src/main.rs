@@ -6,22 +6,17 @@ use itertools::Itertools;
 use nalgebra as na;
 use rand::prelude::*;
 use std::iter::once;
+use std::sync::atomic::AtomicBool;
 
 /// sides related by ! are opposite,
 /// rather than by -, so that we can 0 index.
 /// lives in -dim..=dim-1
 /// eg for dim=3, it would be in [-3, -2, -1, 0, 1, 2]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(transparent)]
 struct Side(i16);
 impl Side {
     fn new(side: i16) -> Self {
-        debug_assert!(
-            side >= -(App::MAX_DIM as i16) && side < App::MAX_DIM as i16,
-            "side should be in -{}..={}",
-            App::MAX_DIM,
-            App::MAX_DIM - 1
-        );
         Side(side)
     }
 
@@ -64,33 +59,37 @@ impl Side {
         }
     }
 
-    fn name(self) -> char {
-        const POS_NAMES: &[char] = &['R', 'U', 'F', 'O', 'A', 'Γ', 'Θ', 'Ξ', 'Σ', 'Ψ'];
-        const NEG_NAMES: &[char] = &['L', 'D', 'B', 'I', 'P', 'Δ', 'Λ', 'Π', 'Φ', 'Ω'];
-        *self.get(POS_NAMES, NEG_NAMES)
-    }
+    const POS_NAMES: &[char] = &['R', 'U', 'F', 'O', 'A', 'Γ', 'Θ', 'Ξ', 'Σ', 'Ψ'];
+    const NEG_NAMES: &[char] = &['L', 'D', 'B', 'I', 'P', 'Δ', 'Λ', 'Π', 'Φ', 'Ω'];
+    /// cyrillic letters that don't look like any of the names above, for the sides past them
+    const POS_EXTRA_NAMES: &[char] = &['Б', 'Ж', 'И', 'Ц', 'Ю', 'Я'];
+    const NEG_EXTRA_NAMES: &[char] = &['Ђ', 'Ш', 'Ч', 'Љ', 'Э', 'Ы'];
+    /// the most axes a puzzle can have, so that every side has a name
+    const MAX_AXES: usize = Self::POS_NAMES.len() + Self::POS_EXTRA_NAMES.len();
 
-    const POS_KEYS: &[char] = &['f', 'e', 'r', 't', 'v', 'y', 'n', 'q', ',', '/'];
-    const NEG_KEYS: &[char] = &['s', 'd', 'w', 'g', 'c', 'h', 'b', 'a', 'm', '.'];
-    fn side_key(self) -> char {
-        *self.get(Self::POS_KEYS, Self::NEG_KEYS)
-    }
-    fn try_from_key(dim: u16, key: egui::Key) -> Option<Self> {
-        let key = key.symbol_or_name();
-        if key.len() != 1 {
-            return None;
+    fn name(self) -> char {
+        let axis = self.axis().into_usize();
+        if axis < Self::POS_NAMES.len() {
+            return *self.get(Self::POS_NAMES, Self::NEG_NAMES);
         }
-        let key = key.to_lowercase().chars().next().unwrap();
-        Self::POS_KEYS[..dim as usize]
-            .iter()
-            .position(|&k| k == key)
+        let names = if self.is_positive() {
+            Self::POS_EXTRA_NAMES
+        } else {
+            Self::NEG_EXTRA_NAMES
+        };
+        names
+            .get(axis - Self::POS_NAMES.len())
+            .copied()
+            .unwrap_or('?')
+    }
+    fn try_from_name(name: char) -> Option<Self> {
+        let position = |names: &[char]| names.iter().position(|&n| n == name);
+        let named = Self::POS_NAMES.len();
+        position(Self::POS_NAMES)
             .map(|i| Self(i as i16))
-            .or_else(|| {
-                Self::NEG_KEYS[..dim as usize]
-                    .iter()
-                    .position(|&k| k == key)
-                    .map(|i| Self(!(i as i16)))
-            })
+            .or_else(|| position(Self::NEG_NAMES).map(|i| Self(!(i as i16))))
+            .or_else(|| position(Self::POS_EXTRA_NAMES).map(|i| Self((named + i) as i16)))
+            .or_else(|| position(Self::NEG_EXTRA_NAMES).map(|i| Self(!((named + i) as i16))))
     }
 
     fn color(self) -> Color32 {
@@ -118,7 +117,20 @@ impl Side {
             Color32::from_rgb(66, 212, 245),
             Color32::from_rgb(47, 47, 189),
         ];
-        *self.get(POS_COLORS, NEG_COLORS)
+        let axis = self.axis().into_usize();
+        if axis < POS_COLORS.len() {
+            return *self.get(POS_COLORS, NEG_COLORS);
+        }
+        self.generated_color()
+    }
+
+    /// for sides past the ones with picked colors
+    fn generated_color(self) -> Color32 {
+        let axis = self.axis().into_usize();
+        // spread the hues out by the golden angle, with opposite sides darker
+        let hue = (axis as f32 * 0.618_034).fract();
+        let value = if self.is_positive() { 0.9 } else { 0.55 };
+        egui::ecolor::Hsva::new(hue, 0.75, value, 1.0).into()
     }
 }
 impl std::ops::Not for Side {
@@ -132,17 +144,12 @@ impl std::ops::Not for Side {
 /// like Side, but only for the positive directions
 /// lives in 0..=dim-1
 /// eg for dim=3, it would be in [0, 1, 2]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(transparent)]
 struct Axis(Side);
 impl Axis {
     fn new(axis: i16) -> Self {
         debug_assert!(0 <= axis, "axis should be non-negative");
-        debug_assert!(
-            axis < App::MAX_DIM as _,
-            "axis should be less than {}",
-            App::MAX_DIM
-        );
         Axis(Side(axis))
     }
 
@@ -158,20 +165,14 @@ impl Axis {
         Self::new(axis as i16)
     }
 
-    const AXIS_KEYS: &[char] = &['k', 'j', 'l', 'i', 'u', 'o', 'p', ';', '[', '\''];
-    fn axis_key(self) -> char {
-        Self::AXIS_KEYS[self.into_usize()]
+    /// named after the positive side
+    fn name(self) -> char {
+        self.0.name()
     }
-    fn try_from_key(dim: u16, key: egui::Key) -> Option<Self> {
-        let key = key.symbol_or_name();
-        if key.len() != 1 {
-            return None;
-        }
-        let key = key.to_lowercase().chars().next().unwrap();
-        Self::AXIS_KEYS[..dim as usize]
-            .iter()
-            .position(|&k| k == key)
-            .map(Self::from_usize)
+    fn try_from_name(name: char) -> Option<Self> {
+        Side::try_from_name(name)
+            .filter(|side| side.is_positive())
+            .map(Side::into_axis)
     }
 }
 // impl From<Axis> for Side {
@@ -217,7 +218,7 @@ impl std::ops::Neg for Coord {
 // TODO: rename to layer?
 /// A shape is a \[Cut], so a 2x3x4 would be a \[Cut(2), Cut(3), Cut(4)]
 /// lives in [1, 2, 3, ...]
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct Cut(i16);
 impl Cut {
     /// all possible coords along this axis
@@ -227,6 +228,47 @@ impl Cut {
             .chain(once(self.0))
             .map(Coord)
     }
+
+    /// the slab a coord is in, numbered from 1 at the positive end.
+    /// stickers are in the same slab as their pieces.
+    fn slab(self, coord: Coord) -> i16 {
+        ((self.0 + 1 - coord.0) / 2).clamp(1, self.0)
+    }
+
+    /// the most positions a shape can have, since building a puzzle goes through all of them
+    const MAX_POSITIONS: usize = 1_000_000;
+
+    /// whether a puzzle can be built with this shape without running out of names or time
+    fn is_buildable(shape: &[Cut]) -> bool {
+        !shape.is_empty()
+            && shape.len() <= Side::MAX_AXES
+            && shape.iter().all(|cut| cut.0 >= 1)
+            && shape
+                .iter()
+                .try_fold(1usize, |n, cut| n.checked_mul(cut.0 as usize + 2))
+                .is_some_and(|n| n <= Self::MAX_POSITIONS)
+    }
+
+    /// eg `2x3x4`, which has to be buildable
+    fn parse_shape(text: &str) -> Option<Vec<Cut>> {
+        let shape = text
+            .trim()
+            .split('x')
+            .map(|cut| cut.parse().ok().map(Cut))
+            .collect::<Option<Vec<_>>>()?;
+        Self::is_buildable(&shape).then_some(shape)
+    }
+
+    /// how many stickers a puzzle of `shape` has, without going through its positions.
+    /// each side has a sticker for every piece on it.
+    fn sticker_count(shape: &[Cut]) -> usize {
+        (0..shape.len())
+            .map(|axis| {
+                let others = shape.iter().enumerate().filter(|&(i, _)| i != axis);
+                2 * others.map(|(_, cut)| cut.0 as usize).product::<usize>()
+            })
+            .sum()
+    }
 }
 
 // #[derive(Clone, Debug, PartialEq, Eq)]
@@ -377,6 +419,15 @@ impl Sticker {
             })
             .expect("sticker should be on a side")
     }
+
+    /// the side it's on, then the other sides of its piece, eg "URF"
+    fn name(&self, shape: &[Cut]) -> String {
+        let side = self.side(shape);
+        once(side)
+            .chain(self.piece(shape).sides(shape).filter(|&s| s != side))
+            .map(Side::name)
+            .collect()
+    }
 }
 
 /// at least one of the coords is ±(n-1)
@@ -421,22 +472,128 @@ impl Piece {
         debug_assert!(ret.peek().is_some(), "piece should be on at least one side");
         ret
     }
+
+    /// eg "URF"
+    fn name(&self, shape: &[Cut]) -> String {
+        self.sides(shape).map(Side::name).collect()
+    }
+
+    /// what to call a piece with `sides` stickers in a `dim` dimensional puzzle
+    fn kind_name(dim: usize, sides: usize) -> String {
+        match sides {
+            1 => "center".into(),
+            _ if sides == dim => "corner".into(),
+            _ if sides + 1 == dim => "edge".into(),
+            _ => format!("{sides}c piece"),
+        }
+    }
+
+    /// pieces can only be moved to places with the same orbit
+    fn orbit(&self, shape: &[Cut]) -> Vec<(Cut, i16)> {
+        self.0
+            .0
+            .iter()
+            .zip(shape)
+            .map(|(coord, cut)| (*cut, coord.0.abs()))
+            .sorted_by_key(|&(cut, coord)| (cut.0, coord))
+            .collect()
+    }
+
+    /// what to call each orbit, eg "edges",
+    /// and which one it is if there are multiple orbits of that kind
+    fn orbit_names(shape: &[Cut]) -> HashMap<Vec<(Cut, i16)>, String> {
+        let mut orbits: HashMap<usize, HashSet<Vec<(Cut, i16)>>> = HashMap::new();
+        for piece in Position::all(shape).filter_map(|pos| Piece::try_from(shape, pos)) {
+            orbits
+                .entry(piece.sides(shape).count())
+                .or_default()
+                .insert(piece.orbit(shape));
+        }
+        orbits
+            .iter()
+            .flat_map(|(&kind, kind_orbits)| {
+                kind_orbits.iter().map(move |orbit| {
+                    let mut name = Piece::kind_name(shape.len(), kind) + "s";
+                    if kind_orbits.len() > 1 {
+                        name += &format!(
+                            " {:?}",
+                            orbit.iter().map(|(_cut, coord)| coord).collect_vec()
+                        );
+                    }
+                    (orbit.clone(), name)
+                })
+            })
+            .collect()
+    }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// layers past the end aren't turned, and trailing `false`s are never stored,
+/// so that equal masks compare equal
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 struct LayerMask(Vec<bool>);
 impl LayerMask {
-    // fn new(n: i16) -> Self {
-    //     let mut ret = vec![false; (n as usize - 1) / 2];
-    //     if n > 1 {
-    //         ret[0] = true;
-    //     }
-    //     LayerMask(ret)
-    // }
     fn new() -> Self {
-        let mut ret = vec![false; (App::MAX_LAYERS as usize - 1) / 2];
-        ret[0] = true;
-        LayerMask(ret)
+        LayerMask(vec![true])
+    }
+
+    /// layers are numbered from 1
+    fn contains(&self, layer: usize) -> bool {
+        layer > 0 && self.0.get(layer - 1).copied().unwrap_or(false)
+    }
+
+    /// like `1-3,10`
+    fn from_ranges(text: &str) -> Option<Self> {
+        let mut mask = LayerMask(vec![]);
+        for range in text.split(',') {
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            let (start, end) = (start.parse::<usize>().ok()?, end.parse::<usize>().ok()?);
+            if start == 0 || start > end || end > i16::MAX as usize {
+                return None;
+            }
+            for layer in start..=end {
+                mask.set(layer, true);
+            }
+        }
+        Some(mask)
+    }
+
+    /// whether every layer is on an axis with `cut` layers
+    fn fits(&self, cut: Cut) -> bool {
+        self.0.len() <= cut.0 as usize
+    }
+
+    /// drops the layers past `cut`, turning the first layer if none are left
+    fn trim(&mut self, cut: Cut) {
+        self.0.truncate(cut.0 as usize);
+        while self.0.last() == Some(&false) {
+            self.0.pop();
+        }
+        if self.0.is_empty() {
+            *self = LayerMask::new();
+        }
+    }
+
+    fn set(&mut self, layer: usize, turned: bool) {
+        if self.0.len() < layer {
+            self.0.resize(layer, false);
+        }
+        self.0[layer - 1] = turned;
+        while self.0.last() == Some(&false) {
+            self.0.pop();
+        }
+    }
+
+    /// the slabs turned when turning `side`,
+    /// numbered from 1 at the positive end of the axis
+    fn slabs(&self, shape: &[Cut], side: Side) -> impl Iterator<Item = i16> {
+        let n = shape[side.axis().into_usize()].0;
+        let positive = side.is_positive();
+        self.0
+            .iter()
+            .positions(|&layer| layer)
+            .map(|i| i as i16 + 1)
+            .filter(move |&layer| layer <= n)
+            .map(move |layer| if positive { layer } else { n + 1 - layer })
     }
 }
 
@@ -457,6 +614,12 @@ impl SideTurn {
             to: self.from,
         }
     }
+
+    /// the slabs of the puzzle this turns,
+    /// numbered from 1 at the positive end of the axis
+    fn slabs(&self, shape: &[Cut]) -> impl Iterator<Item = i16> {
+        self.layers.slabs(shape, self.side)
+    }
 }
 
 /// full puzzle rotation
@@ -472,6 +635,21 @@ impl PuzzleTurn {
             to: self.from,
         }
     }
+
+    /// what this does to the axes
+    fn symmetry(&self, shape: &[Cut]) -> Symmetry {
+        let mut ret = Symmetry::identity(shape.len());
+        let (from, to) = (self.from.into_usize(), self.to.into_usize());
+        if shape[from] == shape[to] {
+            ret.0[from] = self.to.into_side();
+            ret.0[to] = !self.from.into_side();
+        } else {
+            // for cuboids, if you can't turn 90 degrees, just turn 180 degrees
+            ret.0[from] = !self.from.into_side();
+            ret.0[to] = !self.to.into_side();
+        }
+        ret
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -486,925 +664,5395 @@ impl Turn {
             Turn::Puzzle(puzzle_turn) => Self::Puzzle(puzzle_turn.inverse()),
         }
     }
-}
 
-// TODO: don't store shape here
-#[derive(Clone, Debug)]
-enum TurnBuilder {
-    Side {
-        shape: Box<[Cut]>,
-        layers: LayerMask,
-        side: Option<Side>,
-        from: Option<Axis>,
-    },
-    Puzzle {
-        shape: Box<[Cut]>,
-        from: Option<Axis>,
-    },
-}
-impl TurnBuilder {
-    fn new(shape: &[Cut]) -> Self {
-        TurnBuilder::Side {
-            shape: shape.into(),
-            layers: LayerMask::new(),
-            side: None,
-            from: None,
-        }
+    fn inverse_seq(turns: &[Turn]) -> Vec<Turn> {
+        turns.iter().rev().map(Turn::inverse).collect()
     }
 
-    /// returns Some if the turn is complete
-    #[inline(never)]
-    fn update(&mut self, key: egui::Key) -> Option<Turn> {
-        if key == egui::Key::Escape {
-            *self = TurnBuilder::new(self.shape());
-            return None;
+    /// parses a sequence of turns, see [`NotationParser`]
+    fn parse_seq(s: &str) -> Result<Vec<Self>, NotationError> {
+        let mut parser = NotationParser::new(s);
+        let ret = parser.seq(&[])?;
+        match parser.peek() {
+            None => Ok(ret),
+            Some(_) => Err(parser.error("a turn")),
         }
-        if key == egui::Key::X {
-            *self = TurnBuilder::Puzzle {
-                shape: self.shape().into(),
-                from: None,
-            };
-            return None;
+    }
+
+    /// like joining with spaces, but repeated turns are written like `R[UF]2`
+    fn format_seq(turns: &[Turn]) -> String {
+        turns
+            .iter()
+            .dedup_with_count()
+            .map(|(count, turn)| {
+                if count == 1 {
+                    turn.to_string()
+                } else {
+                    format!("{turn}{count}")
+                }
+            })
+            .join(" ")
+    }
+
+    /// whether this turns 180 degrees, because the cuts of `from` and `to` are different
+    fn is_half(&self, shape: &[Cut]) -> bool {
+        let (from, to) = match self {
+            Turn::Side(turn) => (turn.from, turn.to),
+            Turn::Puzzle(turn) => (turn.from, turn.to),
+        };
+        shape[from.into_usize()] != shape[to.into_usize()]
+    }
+
+    /// the turn with `from < to` in the same plane, and whether this is its inverse
+    fn canonical(&self) -> (Turn, bool) {
+        match self {
+            Turn::Side(turn) if turn.from > turn.to => (Turn::Side(turn.inverse()), true),
+            Turn::Puzzle(turn) if turn.from > turn.to => (Turn::Puzzle(turn.inverse()), true),
+            _ => (self.clone(), false),
         }
-        if let Ok(key) = key.name().parse::<usize>() {
-            if key == 0 {
-                return None;
-            }
-            // if key > (self.n() as usize - 1) / 2 {
-            //     return None;
-            // }
-            if let TurnBuilder::Puzzle { shape, .. } = self {
-                *self = TurnBuilder::Side {
-                    shape: shape.clone(),
-                    layers: LayerMask::new(),
-                    side: None,
-                    from: None,
-                };
+    }
+
+    /// whether doing `self` then `other` is the same as doing `other` then `self`
+    fn commutes(&self, other: &Turn, shape: &[Cut]) -> bool {
+        match (self, other) {
+            (Turn::Puzzle(turn), other) | (other, Turn::Puzzle(turn)) => {
+                // iff the puzzle turn maps `other` to itself
+                let (image, image_inverted) = turn.symmetry(shape).turn(other).canonical();
+                let (other, other_inverted) = other.canonical();
+                image == other && (image_inverted == other_inverted || other.is_half(shape))
             }
-            match self {
-                TurnBuilder::Side { layers, .. } => {
-                    layers.0[key - 1] = !layers.0[key - 1];
+            (Turn::Side(a), Turn::Side(b)) => {
+                if a.side.axis() != b.side.axis() {
+                    return false;
                 }
-                TurnBuilder::Puzzle { .. } => unreachable!(),
+                // turns in the same plane commute
+                if a.from.min(a.to) == b.from.min(b.to) && a.from.max(a.to) == b.from.max(b.to) {
+                    return true;
+                }
+                let a = a.slabs(shape).collect::<HashSet<_>>();
+                b.slabs(shape).all(|slab| !a.contains(&slab))
             }
-            return None;
         }
-        if let Some(s) = Side::try_from_key(self.shape().len() as _, key) {
-            match self {
-                TurnBuilder::Side { side, from, .. } => {
-                    *side = Some(s);
-                    *from = None;
-                }
-                TurnBuilder::Puzzle { shape, .. } => {
-                    *self = TurnBuilder::Side {
-                        shape: shape.clone(),
-                        layers: LayerMask::new(),
-                        side: Some(s),
-                        from: None,
-                    };
+    }
+
+    /// cancels and merges turns, and sorts commuting turns into a canonical order.
+    /// half turns come out as the same turn twice.
+    #[inline(never)]
+    fn simplify(shape: &[Cut], turns: &[Turn]) -> Vec<Turn> {
+        let mut merged = Vec::new();
+        for turn in turns {
+            Self::merge(shape, &mut merged, turn);
+        }
+        Self::sort_merged(shape, merged)
+    }
+
+    /// adds `turn` to canonical turns and how many times to do them,
+    /// merging it with the last of the same turn if it commutes with everything in between
+    fn merge(shape: &[Cut], merged: &mut Vec<(Turn, u8)>, turn: &Turn) {
+        let (turn, inverted) = turn.canonical();
+        let modulus = if turn.is_half(shape) { 2 } else { 4 };
+        let amount = if inverted { modulus - 1 } else { 1 };
+        for i in (0..merged.len()).rev() {
+            if merged[i].0 == turn {
+                merged[i].1 = (merged[i].1 + amount) % modulus;
+                if merged[i].1 == 0 {
+                    merged.remove(i);
                 }
+                return;
+            }
+            if !merged[i].0.commutes(&turn, shape) {
+                break;
             }
-            return None;
         }
-        match self {
-            TurnBuilder::Side {
-                shape,
-                side,
-                from,
-                layers,
-                ..
-            } => {
-                if let Some(s) = side {
-                    if let Some(f) = from {
-                        if let Some(t) = Axis::try_from_key(shape.len() as _, key) {
-                            let ret = Some(Turn::Side(SideTurn {
-                                side: *s,
-                                from: *f,
-                                to: t,
-                                layers: layers.clone(),
-                            }));
-                            *from = None;
-                            return ret;
-                        }
-                    } else {
-                        *from = Axis::try_from_key(shape.len() as _, key);
-                    }
+        merged.push((turn, amount));
+    }
+
+    /// the second half of `simplify`, after `merge`
+    fn sort_merged(shape: &[Cut], mut ret: Vec<(Turn, u8)>) -> Vec<Turn> {
+        // bubble sort, but only swapping turns that commute
+        let key = |turn: &Turn| match turn {
+            Turn::Side(turn) => (
+                Some((turn.side.axis(), turn.side)),
+                turn.from,
+                turn.to,
+                Some(turn.layers.clone()),
+            ),
+            Turn::Puzzle(turn) => (None, turn.from, turn.to, None),
+        };
+        loop {
+            let mut sorted = true;
+            for i in 1..ret.len() {
+                if key(&ret[i].0) < key(&ret[i - 1].0) && ret[i - 1].0.commutes(&ret[i].0, shape) {
+                    ret.swap(i - 1, i);
+                    sorted = false;
                 }
             }
-            TurnBuilder::Puzzle { shape, from, .. } => {
-                if let Some(f) = from {
-                    if let Some(t) = Axis::try_from_key(shape.len() as _, key) {
-                        let ret = Some(Turn::Puzzle(PuzzleTurn { from: *f, to: t }));
-                        *from = None;
-                        return ret;
-                    }
-                } else {
-                    *from = Axis::try_from_key(shape.len() as _, key);
-                }
+            if sorted {
+                break;
             }
         }
-        None
+
+        ret.into_iter()
+            .flat_map(|(turn, amount)| {
+                if amount == 3 {
+                    vec![turn.inverse()]
+                } else {
+                    vec![turn; amount as usize]
+                }
+            })
+            .collect()
     }
 
-    // TODO: these are used for evil
-    fn shape(&self) -> &[Cut] {
+    /// the number of moves, counting a turn of any amount as 1 and not counting puzzle turns
+    fn count_moves(shape: &[Cut], turns: &[Turn]) -> usize {
+        SimplifiedTurns::new(shape, turns).move_count()
+    }
+
+    /// the number of axes needed to do this turn
+    fn dim(&self) -> usize {
         match self {
-            TurnBuilder::Side { shape, .. } => shape,
-            TurnBuilder::Puzzle { shape, .. } => shape,
+            Turn::Side(SideTurn { side, from, to, .. }) => {
+                side.axis().max(*from).max(*to).into_usize() + 1
+            }
+            Turn::Puzzle(PuzzleTurn { from, to }) => (*from).max(*to).into_usize() + 1,
         }
     }
 }
-
-#[derive(Clone, Debug)]
-enum TurnError {
-    /// `from` and `to` don't define a plane of rotation
-    UndefinedPlane,
-    // Blocked, // for bandaging
-}
-
-#[derive(Clone, Debug, PartialEq, Eq)]
-struct Puzzle {
-    shape: Vec<Cut>,
-    // #[serde(with = "serde_map")]
-    stickers: HashMap<Sticker, Side>,
+/// `Turn::simplify` of the turns so far, kept up to date one turn at a time,
+/// since simplifying all of a long solve on every turn gets slow
+#[derive(Clone, Debug, Default)]
+struct SimplifiedTurns {
+    /// from `Turn::merge`
+    merged: Vec<(Turn, u8)>,
+    /// formatted, when it's first needed
+    text: Option<String>,
 }
-impl Puzzle {
-    #[inline(never)]
-    fn new(shape: &[Cut]) -> Self {
-        Puzzle {
-            shape: shape.to_vec(),
-            stickers: Position::all(shape)
-                .filter_map(|pos| {
-                    Sticker::try_from(shape, pos).map(|sticker| {
-                        let side = sticker.side(shape);
-                        (sticker, side)
-                    })
-                })
-                .collect(),
+impl SimplifiedTurns {
+    fn new(shape: &[Cut], turns: &[Turn]) -> Self {
+        let mut ret = Self::default();
+        for turn in turns {
+            ret.push(shape, turn);
         }
+        ret
     }
 
-    #[inline(never)]
-    fn is_solved(&self) -> bool {
-        let mut pos_colors: Vec<Option<Side>> = vec![None; self.shape.len()];
-        let mut neg_colors: Vec<Option<Side>> = vec![None; self.shape.len()];
-        for (sticker, color) in &self.stickers {
-            let entry = sticker
-                .side(&self.shape)
-                .get_mut(&mut pos_colors, &mut neg_colors);
-            if let Some(old_color) = entry
-                && old_color != color
-            {
-                return false;
-            }
-            *entry = Some(*color);
-        }
-        true
+    fn push(&mut self, shape: &[Cut], turn: &Turn) {
+        Turn::merge(shape, &mut self.merged, turn);
+        self.text = None;
     }
 
-    // #[inline(never)]
-    // fn turn_side(&mut self, turn: &SideTurn) -> Result<(), TurnError> {
-    //     let SideTurn {
-    //         ref layers,
-    //         side,
-    //         from,
-    //         to,
-    //     } = *turn;
-    //     if side == from.into_side()
-    //         || !side == from.into_side()
-    //         || side == to.into_side()
-    //         || !side == to.into_side()
-    //         || from == to
-    //     {
-    //         return Err(TurnError::UndefinedPlane);
-    //     }
-    //     // assert!(from.0 >= 0 && to.0 >= 0);
-    //     // TODO: i don't think this needs to be a hashmap
-    //     let mut new_stickers = Vec::new();
-    //     let mut from_pos = vec![Coord(0); self.shape.len()].into_boxed_slice();
-    //     for pos in self.stickers.keys() {
-    //         // TODO: layer mask
-    //         // if if side.0 >= 0 {
-    //         //     layers.0[pos[side.0 as usize].0 as usize]
-    //         // } else {
-    //         //     layers.0[pos[(!side.0) as usize].0 as usize]
-    //         // } {
-    //         if if side.is_positive() {
-    //             ((self.shape[side.into_usize()].0 - 1)..=self.shape[side.into_usize()].0)
-    //                 .contains(&pos[side.into_usize()].0)
-    //         } else {
-    //             ((-self.shape[(!side).into_usize()].0)..=(1 - self.shape[(!side).into_usize()].0))
-    //                 .contains(&pos[(!side).into_usize()].0)
-    //         } {
-    //             // TODO: compute to_pos instead of from_pos???
-    //             // let mut from_pos = pos.clone();
-    //             // this is actually faster, i checked
-    //             from_pos.clone_from_slice(pos);
+    /// sorting never brings the same turn together, so each merged turn is a move
+    fn move_count(&self) -> usize {
+        self.merged
+            .iter()
+            .filter(|(turn, _)| matches!(turn, Turn::Side(_)))
+            .count()
+    }
 
-    //             // for cuboids, if you can't turn 90 degrees, just turn 180 degrees
-    //             if self.shape[from.into_usize()] == self.shape[to.into_usize()] {
-    //                 from_pos[from.into_usize()] = pos[to.into_usize()];
-    //                 from_pos[to.into_usize()] = -pos[from.into_usize()];
-    //             } else {
-    //                 from_pos[from.into_usize()] = -pos[from.into_usize()];
-    //                 from_pos[to.into_usize()] = -pos[to.into_usize()];
-    //             }
-    //             new_stickers.push((pos.clone(), self.stickers[&from_pos]));
-    //         }
-    //     }
-    //     self.stickers.extend(new_stickers);
-    //     Ok(())
-    // }
-    #[inline(never)]
-    fn turn_side(&mut self, turn: &SideTurn) -> Result<(), TurnError> {
-        let SideTurn {
-            ref layers,
-            side,
-            from,
-            to,
-        } = *turn;
-        if side == from.into_side()
-            || !side == from.into_side()
-            || side == to.into_side()
-            || !side == to.into_side()
-            || from == to
-        {
-            return Err(TurnError::UndefinedPlane);
+    fn text(&mut self, shape: &[Cut]) -> &str {
+        self.text
+            .get_or_insert_with(|| Turn::format_seq(&Turn::sort_merged(shape, self.merged.clone())))
+    }
+}
+
+impl std::fmt::Display for Turn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Turn::Side(SideTurn {
+                layers,
+                side,
+                from,
+                to,
+            }) => {
+                if *layers != LayerMask::new() {
+                    write!(
+                        f,
+                        "{{{}}}",
+                        layers.0.iter().positions(|&l| l).map(|i| i + 1).join(",")
+                    )?;
+                }
+                write!(f, "{}[{}{}]", side.name(), from.name(), to.name())
+            }
+            Turn::Puzzle(PuzzleTurn { from, to }) => {
+                write!(f, "*[{}{}]", from.name(), to.name())
+            }
         }
-        let mut new_stickers = Vec::new();
-        let mut from_pos = Sticker(Position(vec![Coord(0); self.shape.len()]));
-        for (pos, old_color) in &self.stickers {
-            // TODO: layer mask
-            // if if side.0 >= 0 {
-            //     layers.0[pos[side.0 as usize].0 as usize]
-            // } else {
-            //     layers.0[pos[(!side.0) as usize].0 as usize]
-            // } {
-            // TODO: rename pos to sticker
-            if if side.is_positive() {
-                ((self.shape[side.into_usize()].0 - 1)..=self.shape[side.into_usize()].0)
-                    .contains(&pos.0.0[side.into_usize()].0)
-            } else {
-                ((-self.shape[(!side).into_usize()].0)..=(1 - self.shape[(!side).into_usize()].0))
-                    .contains(&pos.0.0[(!side).into_usize()].0)
-            } {
-                // TODO: compute to_pos instead of from_pos???
+    }
+}
 
-                // let mut from_pos = pos.clone();
-                // this is actually faster, i checked
-                from_pos.0.0.clone_from_slice(&pos.0.0);
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct NotationError {
+    /// byte index into the notation
+    index: usize,
+    expected: &'static str,
+}
+impl std::fmt::Display for NotationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "expected {} at {}", self.expected, self.index)
+    }
+}
 
-                // for cuboids, if you can't turn 90 degrees, just turn 180 degrees
-                if self.shape[from.into_usize()] == self.shape[to.into_usize()] {
-                    from_pos.0.0[from.into_usize()] = pos.0.0[to.into_usize()];
-                    from_pos.0.0[to.into_usize()] = -pos.0.0[from.into_usize()];
-                } else {
-                    from_pos.0.0[from.into_usize()] = -pos.0.0[from.into_usize()];
-                    from_pos.0.0[to.into_usize()] = -pos.0.0[to.into_usize()];
+/// parses whitespace separated turns, eg `R[UF]`, `{1,2}R[UF]`, or `*[UF]` for a puzzle turn, with
+/// - `(A)` for grouping
+/// - `A'` for the inverse of `A`
+/// - `A2` for `A A`
+/// - `[A, B]` for the commutator `A B A' B'`
+/// - `[A: B]` for the conjugate `A B A'`
+struct NotationParser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    len: usize,
+}
+impl<'a> NotationParser<'a> {
+    /// so that something like `(R[UF] U[RF])99999999` doesn't run out of memory
+    const MAX_TURNS: usize = 100_000;
+
+    fn new(s: &'a str) -> Self {
+        NotationParser {
+            chars: s.char_indices().peekable(),
+            len: s.len(),
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|&(_, c)| c)
+    }
+
+    fn error(&mut self, expected: &'static str) -> NotationError {
+        NotationError {
+            index: self.chars.peek().map_or(self.len, |&(i, _)| i),
+            expected,
+        }
+    }
+
+    /// consumes `c` if it's next
+    fn eat(&mut self, c: char) -> bool {
+        self.chars.next_if(|&(_, next)| next == c).is_some()
+    }
+
+    fn expect(&mut self, c: char, expected: &'static str) -> Result<(), NotationError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(expected))
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, NotationError> {
+        let mut ret: Option<usize> = None;
+        while let Some(digit) = self.peek().and_then(|c| c.to_digit(10)) {
+            ret = Some(
+                ret.unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|ret| ret.checked_add(digit as usize))
+                    .ok_or_else(|| self.error("a smaller number"))?,
+            );
+            self.chars.next();
+        }
+        ret.ok_or_else(|| self.error("a number"))
+    }
+
+    /// parses until the end or one of `terminators`, which isn't consumed
+    fn seq(&mut self, terminators: &[char]) -> Result<Vec<Turn>, NotationError> {
+        let mut ret = Vec::new();
+        loop {
+            while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+            match self.peek() {
+                Some(c) if !terminators.contains(&c) => {
+                    ret.extend(self.item()?);
+                    if ret.len() > Self::MAX_TURNS {
+                        return Err(self.error("fewer turns"));
+                    }
                 }
-                new_stickers.push((old_color as *const _, self.stickers[&from_pos]));
+                _ => return Ok(ret),
             }
         }
-        for (old_sticker, new_sticker) in new_stickers {
-            // # Safety: old sticker is where the entry was,
-            // and we didn't modify the map so it should still be there.
-            unsafe {
-                *(old_sticker as *mut _) = new_sticker;
+    }
+
+    fn item(&mut self) -> Result<Vec<Turn>, NotationError> {
+        let mut ret = match self.peek() {
+            Some('(') => {
+                self.chars.next();
+                let ret = self.seq(&[')'])?;
+                self.expect(')', "`)`")?;
+                ret
+            }
+            Some('[') => {
+                self.chars.next();
+                let a = self.seq(&[',', ':'])?;
+                let ret = if self.eat(',') {
+                    let b = self.seq(&[']'])?;
+                    [
+                        a.clone(),
+                        b.clone(),
+                        Turn::inverse_seq(&a),
+                        Turn::inverse_seq(&b),
+                    ]
+                    .concat()
+                } else if self.eat(':') {
+                    let b = self.seq(&[']'])?;
+                    [a.clone(), b, Turn::inverse_seq(&a)].concat()
+                } else {
+                    return Err(self.error("`,` or `:`"));
+                };
+                self.expect(']', "`]`")?;
+                ret
+            }
+            _ => vec![self.turn()?],
+        };
+        loop {
+            match self.peek() {
+                Some('\'') => {
+                    self.chars.next();
+                    ret = Turn::inverse_seq(&ret);
+                }
+                Some(c) if c.is_ascii_digit() => {
+                    let err = self.error("fewer repetitions");
+                    let n = self.number()?;
+                    if ret.len().saturating_mul(n) > Self::MAX_TURNS {
+                        return Err(err);
+                    }
+                    ret = (0..n).flat_map(|_| ret.iter().cloned()).collect();
+                }
+                _ => return Ok(ret),
             }
         }
-        Ok(())
     }
 
-    #[inline(never)]
-    fn turn_puzzle(&mut self, turn: &PuzzleTurn) -> Result<(), TurnError> {
-        let PuzzleTurn { from, to } = *turn;
+    fn turn(&mut self) -> Result<Turn, NotationError> {
+        let layers = if self.eat('{') {
+            let mut mask = LayerMask(vec![]);
+            loop {
+                let err = self.error("a layer");
+                let layer = self.number()?;
+                if layer == 0 || layer > i16::MAX as usize {
+                    return Err(err);
+                }
+                mask.set(layer, true);
+                if self.eat('}') {
+                    break;
+                }
+                self.expect(',', "`,` or `}`")?;
+            }
+            Some(mask)
+        } else {
+            None
+        };
+        let side = if layers.is_none() && self.eat('*') {
+            None
+        } else {
+            let side = self.peek().and_then(Side::try_from_name);
+            let side = side.ok_or_else(|| self.error("a side"))?;
+            self.chars.next();
+            Some(side)
+        };
+        self.expect('[', "`[`")?;
+        let mut axis = || {
+            let axis = self.peek().and_then(Axis::try_from_name);
+            let axis = axis.ok_or_else(|| self.error("an axis"))?;
+            self.chars.next();
+            Ok(axis)
+        };
+        let from = axis()?;
+        let to = axis()?;
+        self.expect(']', "`]`")?;
+        Ok(match side {
+            Some(side) => Turn::Side(SideTurn {
+                layers: layers.unwrap_or_else(LayerMask::new),
+                side,
+                from,
+                to,
+            }),
+            None => Turn::Puzzle(PuzzleTurn { from, to }),
+        })
+    }
+}
+
+/// a symmetry of the puzzle, as a signed permutation of the axes.
+/// `Axis(i)` gets sent to `self.0[i]`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Symmetry(Vec<Side>);
+impl Symmetry {
+    fn identity(dim: usize) -> Self {
+        Symmetry((0..dim).map(|i| Side::new(i as i16)).collect())
+    }
+
+    /// a puzzle rotation taking `from` to `to`,
+    /// or None if there isn't one for this shape
+    fn taking(shape: &[Cut], from: Side, to: Side) -> Option<Self> {
+        let mut ret = Self::identity(shape.len());
         if from == to {
-            return Err(TurnError::UndefinedPlane);
+            return Some(ret);
         }
-        let mut new_stickers = Vec::new();
-        for pos in self.stickers.keys() {
-            let mut from_pos = pos.clone();
-            from_pos.0.0[from.into_usize()] = pos.0.0[to.into_usize()];
-            from_pos.0.0[to.into_usize()] = -pos.0.0[from.into_usize()];
-            new_stickers.push((pos.clone(), self.stickers[&from_pos]));
+        let (a, b) = (from.axis(), to.axis());
+        if a.into_usize() >= shape.len() || b.into_usize() >= shape.len() {
+            return None;
         }
-        self.stickers = HashMap::from_iter(new_stickers);
-        Ok(())
+        if a == b {
+            // 180 degrees in the plane of `a` and any other axis
+            let c = (0..shape.len()).find(|&c| c != a.into_usize())?;
+            ret.0[a.into_usize()] = !a.into_side();
+            ret.0[c] = !Axis::from_usize(c).into_side();
+            return Some(ret);
+        }
+        if shape[a.into_usize()] != shape[b.into_usize()] {
+            return None;
+        }
+        // a puzzle turn from `a` to `b` sends +a to +b and +b to -a
+        let (a, b) = if from.is_positive() == to.is_positive() {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        ret.0[a.into_usize()] = b.into_side();
+        ret.0[b.into_usize()] = !a.into_side();
+        Some(ret)
     }
 
-    #[inline(never)]
-    fn turn(&mut self, turn: &Turn) -> Result<(), TurnError> {
-        match turn {
-            Turn::Side(turn) => self.turn_side(turn),
-            Turn::Puzzle(turn) => self.turn_puzzle(turn),
+    /// the reflection swapping the two sides of `axis`
+    fn mirror(dim: usize, axis: Axis) -> Self {
+        let mut ret = Self::identity(dim);
+        ret.0[axis.into_usize()] = !axis.into_side();
+        ret
+    }
+
+    /// the axes of each size, which rotations can swap with each other
+    fn equal_axes(shape: &[Cut]) -> Vec<Vec<usize>> {
+        (0..shape.len())
+            .sorted_by_key(|&i| shape[i])
+            .chunk_by(|&i| shape[i])
+            .into_iter()
+            .map(|(_, axes)| axes.collect())
+            .collect()
+    }
+
+    /// how many rotations take the shape to itself, or None if it's too many to count
+    fn rotation_count(shape: &[Cut]) -> Option<usize> {
+        // every signed permutation of each group of equal axes, half of which are reflections
+        Self::equal_axes(shape)
+            .iter()
+            .try_fold(1usize, |n, axes| {
+                let k = axes.len();
+                (1..=k).try_fold(n, |n, i| n.checked_mul(2 * i))
+            })
+            .map(|n| n / 2)
+    }
+
+    /// every rotation taking the shape to itself
+    fn rotations(shape: &[Cut]) -> Vec<Self> {
+        let groups = Self::equal_axes(shape);
+        groups
+            .iter()
+            .map(|axes| axes.iter().copied().permutations(axes.len()))
+            .multi_cartesian_product()
+            .map(|images| {
+                let mut perm = vec![0; shape.len()];
+                for (axes, images) in groups.iter().zip(images) {
+                    for (&i, j) in axes.iter().zip(images) {
+                        perm[i] = j;
+                    }
+                }
+                perm
+            })
+            .flat_map(|perm| {
+                let inversions = perm
+                    .iter()
+                    .tuple_combinations()
+                    .filter(|(a, b)| a > b)
+                    .count();
+                (0..1u32 << shape.len()).filter_map(move |flips| {
+                    // reflections aren't rotations
+                    if (inversions as u32 + flips.count_ones()) % 2 == 1 {
+                        return None;
+                    }
+                    Some(Symmetry(
+                        perm.iter()
+                            .enumerate()
+                            .map(|(i, &j)| {
+                                let side = Side::new(j as i16);
+                                if flips >> i & 1 == 1 { !side } else { side }
+                            })
+                            .collect(),
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    fn inverse(&self) -> Self {
+        let mut ret = self.clone();
+        for (i, side) in self.0.iter().enumerate() {
+            let axis = Axis::from_usize(i).into_side();
+            ret.0[side.axis().into_usize()] = if side.is_positive() { axis } else { !axis };
         }
+        ret
     }
 
-    #[inline(never)]
-    fn scramble(&mut self, rng: &mut impl Rng) {
-        const SCRAMBLE_N: usize = 1000;
-        let start = std::time::Instant::now();
-        let dim = self.shape.len() as i16;
-        let side_dist = rand::distr::Uniform::new(-dim, dim).unwrap();
-        let axis_dist = rand::distr::Uniform::new(0, dim).unwrap();
-        for _ in 0..SCRAMBLE_N {
-            // TODO: layer mask
-            let side = rng.sample(side_dist);
-            let from = rng.sample(axis_dist);
-            if side == from || !side == from {
-                continue;
+    /// doing `other` then `self`
+    fn compose(&self, other: &Symmetry) -> Self {
+        Symmetry(other.0.iter().map(|&side| self.side(side)).collect())
+    }
+
+    /// as a rotation matrix of nd coordinates
+    fn matrix(&self) -> na::DMatrix<f32> {
+        let dim = self.0.len();
+        let mut ret = na::DMatrix::zeros(dim, dim);
+        for (i, side) in self.0.iter().enumerate() {
+            ret[(side.axis().into_usize(), i)] = if side.is_positive() { 1.0 } else { -1.0 };
+        }
+        ret
+    }
+
+    fn side(&self, side: Side) -> Side {
+        if side.is_positive() {
+            self.0[side.into_usize()]
+        } else {
+            !self.0[(!side).into_usize()]
+        }
+    }
+
+    /// the turn that does to the transformed puzzle what `turn` does to the original
+    fn turn(&self, turn: &Turn) -> Turn {
+        // if exactly one of from and to gets flipped,
+        // then the rotation goes the other way around
+        let plane = |from: Axis, to: Axis| {
+            let (from, to) = (self.side(from.into_side()), self.side(to.into_side()));
+            if from.is_positive() == to.is_positive() {
+                (from.axis(), to.axis())
+            } else {
+                (to.axis(), from.axis())
             }
-            let to = rng.sample(axis_dist);
-            if side == to || !side == to || from == to {
-                continue;
+        };
+        match turn {
+            Turn::Side(SideTurn {
+                layers,
+                side,
+                from,
+                to,
+            }) => {
+                let (from, to) = plane(*from, *to);
+                Turn::Side(SideTurn {
+                    layers: layers.clone(),
+                    side: self.side(*side),
+                    from,
+                    to,
+                })
+            }
+            Turn::Puzzle(PuzzleTurn { from, to }) => {
+                let (from, to) = plane(*from, *to);
+                Turn::Puzzle(PuzzleTurn { from, to })
             }
-            self.turn_side(&SideTurn {
-                layers: LayerMask::new(),
-                side: Side(side),
-                from: Axis::new(from),
-                to: Axis::new(to),
-            })
-            .unwrap();
         }
-        println!("scrambled  in {:?}", start.elapsed());
     }
 }
 
-/// mapping from Pos to (x, y) coordinates
-/// +x is right, +y is up
-#[derive(Clone, Debug)]
-struct Layout2dBuilder {
-    width: usize,
-    height: usize,
-    mapping: Vec<(Vec<Coord>, (usize, usize))>,
+/// the keys for gripping sides and picking axes
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Keybinds {
+    pos_sides: Vec<char>,
+    neg_sides: Vec<char>,
+    axes: Vec<char>,
+    /// switches to turning the whole puzzle
+    puzzle: char,
 }
-impl Layout2dBuilder {
-    #[inline(never)]
-    fn new(shape: &[Cut]) -> Self {
-        if shape.is_empty() {
-            return Self {
-                width: 1,
-                height: 1,
-                mapping: vec![(Vec::new(), (0, 0))],
-            };
+impl Keybinds {
+    fn new() -> Self {
+        Keybinds {
+            pos_sides: vec!['f', 'e', 'r', 't', 'v', 'y', 'n', 'q', ',', '/'],
+            neg_sides: vec!['s', 'd', 'w', 'g', 'c', 'h', 'b', 'a', 'm', '.'],
+            axes: vec!['k', 'j', 'l', 'i', 'u', 'o', 'p', ';', '[', '\''],
+            puzzle: 'x',
         }
-        // TODO: refactor to use Position
-        let horizontal = shape.len() % 2 == 1;
-        let lower = Self::new(&shape[..shape.len() - 1]);
-        let last = *shape.last().unwrap();
-        assert_eq!(last.coords().count(), last.0 as usize + 2);
-        let mut ret = Self {
-            width: 0,
-            height: 0,
-            mapping: Vec::with_capacity(lower.mapping.len() * (last.0 as usize + 2)),
-        };
-        for (i, new_coord) in last.coords().enumerate() {
-            let mut lower = lower.clone();
-            lower.mapping = lower
-                .mapping
-                .into_iter()
-                .map(|(mut pos, xy)| {
-                    pos.push(new_coord);
-                    (pos, xy)
-                })
-                .collect();
-            if new_coord.0.abs() == last.0 {
-                // only keep valid positions
-                // we only need to check this if it's a cap
-                lower.mapping.retain(|(pos, _xy)| {
-                    pos.iter()
-                        .zip(shape)
-                        .filter(|(coord, cut)| coord.0.abs() == cut.0)
-                        .count()
-                        <= 1
-                });
-                // TODO: possibly shrink margins
-            } else {
-                debug_assert!(lower.mapping.iter().all(|(pos, _xy)| {
-                    pos.iter()
-                        .zip(shape)
-                        .filter(|(coord, cut)| coord.0.abs() == cut.0)
-                        .count()
-                        <= 1
-                }));
-            }
-            let shift = if horizontal {
-                lower.width
-            } else {
-                lower.height
-            };
-            let shift = if shape.len() > 2 {
-                (shift + 1) * i
-            } else {
-                shift * i
-            };
-            if horizontal {
-                lower.right(shift);
-            } else {
-                lower.down(shift);
-            }
-            ret.union(lower);
-        }
-        assert!(ret.mapping.len() <= lower.mapping.len() * (last.0 as usize + 2));
-        ret
     }
 
-    #[inline(never)]
-    fn union(&mut self, other: Self) {
-        self.width = self.width.max(other.width);
-        self.height = self.height.max(other.height);
-        let self_len = self.mapping.len();
-        let other_len = other.mapping.len();
-        self.mapping.extend(other.mapping);
-        assert_eq!(self.mapping.len(), self_len + other_len);
+    /// the lowercase character of a key, if it has one
+    fn char_of(key: egui::Key) -> Option<char> {
+        // egui only has a name for this one
+        if key == egui::Key::Quote {
+            return Some('\'');
+        }
+        let key = key.symbol_or_name();
+        if key.len() != 1 {
+            return None;
+        }
+        key.to_lowercase().chars().next()
     }
 
-    #[inline(never)]
-    fn right(&mut self, shift: usize) {
-        self.mapping.iter_mut().for_each(|(_pos, (x, _y))| {
-            *x += shift;
-        });
-        self.width += shift;
+    /// axes past the end of a list use its keys again with shift held,
+    /// and axes past that don't have a key
+    fn layered(keys: &[char], index: usize) -> Option<(char, bool)> {
+        let key = *keys.get(index.checked_sub(keys.len()).unwrap_or(index))?;
+        Some((key, index >= keys.len()))
     }
 
-    #[inline(never)]
-    fn down(&mut self, shift: usize) {
-        self.mapping.iter_mut().for_each(|(_pos, (_x, y))| {
-            *y += shift;
-        });
-        self.height += shift;
+    /// the index a key picks in a list, if it's below `dim`
+    fn index_of(keys: &[char], dim: usize, key: char, shift: bool) -> Option<usize> {
+        let index = keys.iter().position(|&k| k == key)? + if shift { keys.len() } else { 0 };
+        (index < dim).then_some(index)
     }
-}
 
-#[derive(Clone, Debug)]
-struct Layout2d {
-    width: usize,
-    height: usize,
-    mapping: HashMap<Position, (usize, usize)>,
-    inverse: HashMap<(usize, usize), Position>,
-}
-impl Layout2d {
-    #[inline(never)]
-    fn new(shape: &[Cut]) -> Self {
-        let mapping = Layout2dBuilder::new(shape).mapping;
-        // shrink the margins
-        let x_min = mapping.iter().map(|(_pos, (x, _y))| *x).min().unwrap();
-        let y_min = mapping.iter().map(|(_pos, (_x, y))| *y).min().unwrap();
-        let x_max = mapping.iter().map(|(_pos, (x, _y))| *x).max().unwrap();
-        let y_max = mapping.iter().map(|(_pos, (_x, y))| *y).max().unwrap();
-        Layout2d {
-            width: x_max - x_min + 1,
-            height: y_max - y_min + 1,
-            mapping: mapping
-                .clone()
-                .into_iter()
-                .map(|(pos, (x, y))| (Position::new(shape, pos), (x - x_min, y - y_min)))
-                .collect(),
-            inverse: mapping
-                .into_iter()
-                .map(|(pos, (x, y))| ((x - x_min, y - y_min), Position::new(shape, pos)))
-                .collect(),
+    fn label((key, shift): (char, bool)) -> String {
+        if shift {
+            format!("⇧{key}")
+        } else {
+            key.to_string()
         }
     }
-}
-
-// /// maps a nd point to a (n-1)d point
-// #[derive(Clone, Debug)]
-// struct CameraTo3dStage {
-//     dim: usize,
-//     rot: na::Matrix<f32, na::Dyn, na::Dyn, na::VecStorage<f32, na::Dyn, na::Dyn>>,
-// }
-// impl CameraTo3dStage {
-//     fn new(dim: usize) -> Self {
-//         let golden_ratio = (1.0 + 5.0_f32.sqrt()) / 2.0;
-//         let mut rot = na::DMatrix::<f32>::identity(dim, dim);
-//         let last = dim - 1;
-//         for i in 0..last {
-//             let angle = std::f32::consts::FRAC_PI_4 / golden_ratio.powi(i as i32);
-//             let (s, c) = angle.sin_cos();
-//             // Right-multiply rot by G(i, last, angle):
-//             //   new col_i    =  c * col_i + s * col_last
-//             //   new col_last = -s * col_i + c * col_last
-//             let col_i: Vec<f32> = (0..dim).map(|r| rot[(r, i)]).collect();
-//             let col_last: Vec<f32> = (0..dim).map(|r| rot[(r, last)]).collect();
-//             for r in 0..dim {
-//                 rot[(r, i)] = c * col_i[r] + s * col_last[r];
-//                 rot[(r, last)] = -s * col_i[r] + c * col_last[r];
-//             }
-//         }
-//         Self { dim, rot }
-//     }
-
-//     /// returns (projected position, depth)
-//     fn project(&self, pos: &[f32]) -> (Box<[f32]>, f32) {
-//         assert_eq!(pos.len(), self.dim);
-//         let pos = &self.rot * na::DVector::from_column_slice(pos);
-//         let pos = pos.as_slice();
-//         // hack to test 4d perspective projection
-//         if let [x, y, z, w] = pos {
-//             let f = 1.0 / (1.0 + (1.0 - w));
-//             // let f = 1.0 / (1.0 + w);
-//             (vec![x * f, y * f, z * f].into(), *w)
-//         } else {
-//             let (last, pos) = pos.split_last().unwrap();
-//             (pos.into(), *last)
-//         }
-//     }
-// }
-
-// /// maps a nd point to a 3d point
-// #[derive(Clone, Debug)]
-// struct CameraTo3d {
-//     stages: Vec<CameraTo3dStage>,
-// }
-// impl CameraTo3d {
-//     fn new(dim: usize) -> Self {
-//         let stages = (4..=dim).rev().map(CameraTo3dStage::new).collect();
-//         CameraTo3d { stages }
-//     }
-
-//     fn project(&self, pos: &[f32]) -> Box<[f32]> {
-//         if self.stages.is_empty() {
-//             assert_eq!(pos.len(), 3);
-//             return pos.into();
-//         }
-//         assert_eq!(self.stages.first().unwrap().dim, pos.len());
-//         assert_eq!(self.stages.last().unwrap().dim, 4);
-//         let mut pos = pos.to_vec().into_boxed_slice();
-//         for stage in &self.stages {
-//             let (new_pos, depth) = stage.project(&pos);
-//             pos = new_pos;
-//         }
-//         pos
-//     }
-// }
 
-/// maps a nd point to a 4d point via a orthographic projection.
-#[derive(Clone, Debug)]
-struct CameraNdTo4d {
-    rot: na::Matrix<f32, na::U4, na::Dyn, na::VecStorage<f32, na::U4, na::Dyn>>,
-}
-impl CameraNdTo4d {
-    fn new(dim: usize) -> Self {
-        let golden_ratio = (1.0 + 5.0_f32.sqrt()) / 2.0;
-        let mut rot: na::Matrix<f32, na::U4, na::Dyn, na::VecStorage<f32, na::U4, na::Dyn>> =
-            na::Matrix::<f32, na::U4, na::Dyn, na::VecStorage<f32, na::U4, na::Dyn>>::identity(dim);
-        let last = dim - 1;
-        for i in 0..last {
-            let angle = std::f32::consts::FRAC_PI_4 / golden_ratio.powi(i as i32);
-            let (s, c) = angle.sin_cos();
-            // Right-multiply rot by G(i, last, angle):
-            //   new col_i    =  c * col_i + s * col_last
-            //   new col_last = -s * col_i + c * col_last
-            let col_i: Vec<f32> = (0..dim).map(|r| rot[(r, i)]).collect();
-            let col_last: Vec<f32> = (0..dim).map(|r| rot[(r, last)]).collect();
-            for r in 0..dim {
-                rot[(r, i)] = c * col_i[r] + s * col_last[r];
-                rot[(r, last)] = -s * col_i[r] + c * col_last[r];
-            }
+    fn side_key(&self, side: Side) -> Option<String> {
+        let axis = side.axis().into_usize();
+        if side.is_positive() {
+            Self::layered(&self.pos_sides, axis).map(Self::label)
+        } else {
+            Self::layered(&self.neg_sides, axis).map(Self::label)
         }
+    }
 
-        Self { rot }
+    fn axis_key(&self, axis: Axis) -> Option<String> {
+        Self::layered(&self.axes, axis.into_usize()).map(Self::label)
     }
 
-    /// \[x, y, z, w]
-    fn project(&self, pos: &[f32]) -> [f32; 4] {
-        let pos = &self.rot * na::DVector::from_column_slice(pos);
-        *pos.as_slice().as_array::<4>().unwrap()
+    fn side(&self, dim: usize, key: egui::Key, shift: bool) -> Option<Side> {
+        let key = Self::char_of(key)?;
+        Self::index_of(&self.pos_sides, dim, key, shift)
+            .map(|i| Side::new(i as i16))
+            .or_else(|| {
+                Self::index_of(&self.neg_sides, dim, key, shift).map(|i| !Side::new(i as i16))
+            })
     }
-}
 
-/// maps a 4d point to a 3d point via a perspective projection.
-#[derive(Clone, Debug)]
-struct Camera4dTo3d {
-    rot: na::Matrix4<f32>,
-}
-impl Camera4dTo3d {
-    fn new() -> Self {
-        Self {
-            rot: na::Matrix4::identity(),
-        }
+    fn axis(&self, dim: usize, key: egui::Key, shift: bool) -> Option<Axis> {
+        let key = Self::char_of(key)?;
+        Self::index_of(&self.axes, dim, key, shift).map(Axis::from_usize)
     }
 
-    /// (\[x, y, z], depth)
-    fn project(&self, pos: &[f32; 4]) -> ([f32; 3], f32) {
-        for x in pos {
-            assert!(x.is_finite());
-        }
-        let pos = self.rot * na::DVector::from_column_slice(pos);
-        let [x, y, z, w] = *pos.as_slice().as_array().unwrap();
-        let f = 1.0 / (2.0 - w);
-        assert!(x.is_finite());
-        assert!(y.is_finite());
-        assert!(z.is_finite());
-        assert!(w.is_finite());
-        assert!(f.is_finite());
-        // ([x * f, y * f, z * f], w)
-        ([x * f, y * f, z * f], 1.0 / f)
+    /// every key is used once, can be pressed, and isn't a digit, which pick layers.
+    /// keys are compared lowercase, so uppercase letters could never be pressed.
+    fn is_valid(&self) -> bool {
+        let keys = [&self.pos_sides, &self.neg_sides, &self.axes]
+            .into_iter()
+            .flatten()
+            .chain(once(&self.puzzle))
+            .collect::<Vec<_>>();
+        let pressable = |key: char| {
+            egui::Key::ALL
+                .iter()
+                .any(|&k| Self::char_of(k) == Some(key))
+        };
+        keys.iter()
+            .all(|&&key| !key.is_ascii_digit() && pressable(key))
+            && keys.iter().all_unique()
     }
 }
 
-/// maps a 3d point to a 2d point via a perspective projection.
+// TODO: don't store shape here
 #[derive(Clone, Debug)]
-struct Camera3dTo2d {
-    rot: na::Matrix3<f32>,
+enum TurnBuilder {
+    Side {
+        shape: Box<[Cut]>,
+        layers: LayerMask,
+        side: Option<Side>,
+        from: Option<Axis>,
+        /// layers being typed after pressing 0, for layers past 9, like `10-12,15`
+        entry: Option<String>,
+    },
+    Puzzle {
+        shape: Box<[Cut]>,
+        from: Option<Axis>,
+    },
 }
-impl Camera3dTo2d {
-    fn new() -> Self {
-        Self {
-            rot: na::Matrix3::identity(),
+impl TurnBuilder {
+    fn new(shape: &[Cut]) -> Self {
+        TurnBuilder::Side {
+            shape: shape.into(),
+            layers: LayerMask::new(),
+            side: None,
+            from: None,
+            entry: None,
         }
     }
 
-    // (\[x, y], depth)
-    fn project(&self, pos: &[f32; 3]) -> ([f32; 2], f32) {
-        for x in pos {
-            assert!(x.is_finite());
+    /// returns Some if the turn is complete, shift picks sides and axes past the key lists
+    #[inline(never)]
+    fn update(&mut self, key: egui::Key, shift: bool, keybinds: &Keybinds) -> Option<Turn> {
+        if key == egui::Key::Escape {
+            *self = TurnBuilder::new(self.shape());
+            return None;
         }
-        let pos = self.rot * na::Vector3::new(pos[0], pos[1], pos[2]);
-        let [x, y, z] = *pos.as_slice().as_array().unwrap();
-        // let x = pos[0] / (1.0 + pos[2]);
-        // let y = pos[1] / (1.0 + pos[2]);
-        assert!(x.is_finite());
-        assert!(y.is_finite());
-        assert!(z.is_finite());
-        ([x, y], z)
+        if let TurnBuilder::Side {
+            shape,
+            layers,
+            side,
+            entry,
+            ..
+        } = self
+            && let Some(text) = entry
+        {
+            match key {
+                egui::Key::Minus => text.push('-'),
+                egui::Key::Comma => text.push(','),
+                egui::Key::Backspace => {
+                    text.pop();
+                }
+                // only finish if the layers are all on the gripped axis,
+                // or nothing was typed, which keeps the layers picked before
+                egui::Key::Enter => {
+                    if text.is_empty() {
+                        *entry = None;
+                    } else if let Some(mask) = LayerMask::from_ranges(text)
+                        && side.is_none_or(|side| mask.fits(shape[side.axis().into_usize()]))
+                    {
+                        *layers = mask;
+                        *entry = None;
+                    }
+                }
+                _ => {
+                    if let Ok(digit) = key.name().parse::<u8>() {
+                        text.push_str(&digit.to_string());
+                    }
+                }
+            }
+            return None;
+        }
+        if Keybinds::char_of(key) == Some(keybinds.puzzle) {
+            *self = TurnBuilder::Puzzle {
+                shape: self.shape().into(),
+                from: None,
+            };
+            return None;
+        }
+        if let Ok(key) = key.name().parse::<usize>() {
+            if let TurnBuilder::Puzzle { shape, .. } = self {
+                *self = TurnBuilder::Side {
+                    shape: shape.clone(),
+                    layers: LayerMask::new(),
+                    side: None,
+                    from: None,
+                    entry: None,
+                };
+            }
+            match self {
+                TurnBuilder::Side {
+                    shape,
+                    layers,
+                    side,
+                    entry,
+                    ..
+                } => {
+                    if key == 0 {
+                        *entry = Some(String::new());
+                    } else if side
+                        .is_none_or(|side| key <= shape[side.axis().into_usize()].0 as usize)
+                    {
+                        layers.set(key, !layers.contains(key));
+                    }
+                }
+                TurnBuilder::Puzzle { .. } => unreachable!(),
+            }
+            return None;
+        }
+        if let Some(s) = keybinds.side(self.shape().len(), key, shift) {
+            match self {
+                // layers picked before the side have to be on its axis
+                TurnBuilder::Side {
+                    shape,
+                    layers,
+                    side,
+                    from,
+                    ..
+                } => {
+                    layers.trim(shape[s.axis().into_usize()]);
+                    *side = Some(s);
+                    *from = None;
+                }
+                TurnBuilder::Puzzle { shape, .. } => {
+                    *self = TurnBuilder::Side {
+                        shape: shape.clone(),
+                        layers: LayerMask::new(),
+                        side: Some(s),
+                        from: None,
+                        entry: None,
+                    };
+                }
+            }
+            return None;
+        }
+        match self {
+            TurnBuilder::Side {
+                shape,
+                side,
+                from,
+                layers,
+                ..
+            } => {
+                if let Some(s) = side {
+                    if let Some(f) = from {
+                        if let Some(t) = keybinds.axis(shape.len(), key, shift) {
+                            let ret = Some(Turn::Side(SideTurn {
+                                side: *s,
+                                from: *f,
+                                to: t,
+                                layers: layers.clone(),
+                            }));
+                            *from = None;
+                            return ret;
+                        }
+                    } else {
+                        *from = keybinds.axis(shape.len(), key, shift);
+                    }
+                }
+            }
+            TurnBuilder::Puzzle { shape, from, .. } => {
+                if let Some(f) = from {
+                    if let Some(t) = keybinds.axis(shape.len(), key, shift) {
+                        let ret = Some(Turn::Puzzle(PuzzleTurn { from: *f, to: t }));
+                        *from = None;
+                        return ret;
+                    }
+                } else {
+                    *from = keybinds.axis(shape.len(), key, shift);
+                }
+            }
+        }
+        None
     }
-}
-
-#[derive(Clone, Debug)]
-struct StickerFormatBuilder {
-    outline_color: Option<Color32>,
-    outline_width: Option<f32>,
-    sticker_scale: Option<f32>,
-    sticker_opacity: Option<f32>,
-}
-impl StickerFormatBuilder {
-    pub const NONE: Self = StickerFormatBuilder {
-        outline_color: None,
-        outline_width: None,
-        sticker_scale: None,
-        sticker_opacity: None,
-    };
 
-    fn update(&mut self, other: &StickerFormatBuilder) {
-        self.outline_color = self.outline_color.or(other.outline_color);
-        self.outline_width = self.outline_width.or(other.outline_width);
-        self.sticker_scale = self.sticker_scale.or(other.sticker_scale);
-        self.sticker_opacity = self.sticker_opacity.or(other.sticker_opacity);
+    /// the turn so far, in notation, and what to pick next
+    fn status(&self) -> String {
+        match self {
+            TurnBuilder::Side {
+                shape,
+                layers,
+                side,
+                from,
+                entry,
+            } => {
+                let layers = format!(
+                    "{{{}}}",
+                    layers.0.iter().positions(|&l| l).map(|i| i + 1).join(",")
+                );
+                // say why enter won't finish
+                if let Some(text) = entry {
+                    return match (text.is_empty(), LayerMask::from_ranges(text), side) {
+                        (false, None, _) => {
+                            format!("{layers}, typing layers {text}, which should be like 1-3,10")
+                        }
+                        (_, Some(mask), Some(side))
+                            if !mask.fits(shape[side.axis().into_usize()]) =>
+                        {
+                            format!(
+                                "{layers}, typing layers {text}, but {} only has {}",
+                                side.name(),
+                                shape[side.axis().into_usize()].0
+                            )
+                        }
+                        _ => format!("{layers}, typing layers {text}, press enter to finish"),
+                    };
+                }
+                match (side, from) {
+                    (None, _) => format!("{layers}, pick a side"),
+                    (Some(side), None) => {
+                        format!("{layers}{}[, pick an axis to turn from", side.name())
+                    }
+                    (Some(side), Some(from)) => format!(
+                        "{layers}{}[{}, pick an axis to turn to",
+                        side.name(),
+                        from.name()
+                    ),
+                }
+            }
+            TurnBuilder::Puzzle { from, .. } => match from {
+                None => "*[, turning the whole puzzle, pick an axis to turn from".into(),
+                Some(from) => format!(
+                    "*[{}, turning the whole puzzle, pick an axis to turn to",
+                    from.name()
+                ),
+            },
+        }
     }
 
-    fn build_or(&self, default: &StickerFormat) -> StickerFormat {
-        StickerFormat {
-            outline_color: self.outline_color.unwrap_or(default.outline_color),
-            outline_width: self.outline_width.unwrap_or(default.outline_width),
-            sticker_scale: self.sticker_scale.unwrap_or(default.sticker_scale),
-            sticker_opacity: self.sticker_opacity.unwrap_or(default.sticker_opacity),
+    // TODO: these are used for evil
+    fn shape(&self) -> &[Cut] {
+        match self {
+            TurnBuilder::Side { shape, .. } => shape,
+            TurnBuilder::Puzzle { shape, .. } => shape,
         }
     }
 }
 
-// TODO: better name
-#[derive(Clone, Debug)]
-struct StickerFormat {
-    outline_color: Color32,
-    /// lives in [0.0, 1.0],
-    /// where 1.0 is the size of a sticker
-    outline_width: f32,
-    /// lives in [0.0, 1.0]
-    sticker_scale: f32,
-    /// lives in [0.0, 1.0]
-    sticker_opacity: f32,
-}
-// impl Default for StickerFormat {
-//     fn default() -> Self {
-//         StickerFormat {
-//             outline_color: Color32::from_rgb(100, 100, 100),
-//             outline_width: 0.02,
-//             sticker_scale: 1.0,
-//             sticker_opacity: 1.0,
-//         }
-//     }
-// }
-
 #[derive(Clone, Debug)]
-struct FilterTerm {
-    must_have: HashSet<Side>,
-    cant_have: HashSet<Side>,
+enum TurnError {
+    /// `from` and `to` don't define a plane of rotation
+    UndefinedPlane,
+    /// a layer past the end of the turned axis, which has `layers` layers
+    LayerOutOfRange { layer: usize, layers: i16 },
+    /// the turned side isn't on a `dim` dimensional puzzle
+    SideOutOfRange { side: Side, dim: usize },
+    /// `from` or `to` isn't on a `dim` dimensional puzzle
+    AxisOutOfRange { axis: Axis, dim: usize },
+    /// pieces that are stuck together are in the way
+    // nothing is bandaged yet
+    #[allow(dead_code)]
+    Blocked,
+    /// the app isn't taking turns right now, for the given reason
+    Disallowed(&'static str),
 }
-impl FilterTerm {
-    fn contains(&self, shape: &[Cut], piece: &Piece) -> bool {
-        for side in piece.sides(shape) {
-            if !self.must_have.contains(&side) {
-                return false;
+impl std::fmt::Display for TurnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TurnError::UndefinedPlane => write!(f, "the axes don't make a plane to turn in"),
+            TurnError::LayerOutOfRange { layer, layers } => {
+                write!(f, "layer {layer} is past the {layers} layers of the side")
             }
-            if self.cant_have.contains(&side) {
-                return false;
+            TurnError::SideOutOfRange { side, dim } => {
+                write!(f, "{} isn't a side of a {dim}d puzzle", side.name())
             }
+            TurnError::AxisOutOfRange { axis, dim } => {
+                write!(f, "{} isn't an axis of a {dim}d puzzle", axis.name())
+            }
+            TurnError::Blocked => write!(f, "bandaged pieces are in the way"),
+            TurnError::Disallowed(reason) => write!(f, "can't turn now, {reason}"),
         }
-        true
     }
 }
 
-/// the filter contains a piece if it matches any of the terms
-#[derive(Clone, Debug)]
-struct Filter {
-    terms: Vec<FilterTerm>,
-    format: StickerFormatBuilder,
+/// `T` is what's on each sticker, normally its color
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Puzzle<T = Side> {
+    shape: Vec<Cut>,
+    // #[serde(with = "serde_map")]
+    stickers: HashMap<Sticker, T>,
 }
-impl Filter {
-    fn contains(&self, shape: &[Cut], piece: &Piece) -> bool {
-        self.terms.iter().any(|term| term.contains(shape, piece))
+impl Puzzle {
+    #[inline(never)]
+    fn new(shape: &[Cut]) -> Self {
+        Puzzle {
+            shape: shape.to_vec(),
+            stickers: Position::all(shape)
+                .filter_map(|pos| {
+                    Sticker::try_from(shape, pos).map(|sticker| {
+                        let side = sticker.side(shape);
+                        (sticker, side)
+                    })
+                })
+                .collect(),
+        }
     }
-}
 
-/// a entire filter stage is rendered at once,
-/// with the last Some property of the filter being applied to that piece
-#[derive(Clone, Debug)]
-struct FilterStage(Vec<Filter>);
-impl FilterStage {
-    // fn try_get(&self, shape: &[Cut], piece: &[Coord]) -> Option<StickerFormatBuilder> {
-    //     for filter in &self.0 {
-    //         if let Some(format) = filter.try_get(shape, piece) {
-    //             return Some(format);
-    //         }
-    //     }
-    //     None
-    // }
+    #[inline(never)]
+    fn is_solved(&self) -> bool {
+        let mut pos_colors: Vec<Option<Side>> = vec![None; self.shape.len()];
+        let mut neg_colors: Vec<Option<Side>> = vec![None; self.shape.len()];
+        for (sticker, color) in &self.stickers {
+            let entry = sticker
+                .side(&self.shape)
+                .get_mut(&mut pos_colors, &mut neg_colors);
+            if let Some(old_color) = entry
+                && old_color != color
+            {
+                return false;
+            }
+            *entry = Some(*color);
+        }
+        true
+    }
 }
+impl Puzzle {
+    /// the name of the side on each sticker, in the order of `Position::all`
+    fn stickers_to_string(&self) -> String {
+        Position::all(&self.shape)
+            .filter_map(|pos| Sticker::try_from(&self.shape, pos))
+            .map(|sticker| self.stickers[&sticker].name())
+            .collect()
+    }
 
-#[derive(Clone, Debug)]
-struct FilterSequence(Vec<FilterStage>);
-// impl FilterSequence {
-//     fn new() -> Self {
-//         FilterSequence(Vec::new())
-//     }
-// }
+    /// the shape and then `stickers_to_string`, eg `2x2x2 RRRRLLLL...`,
+    /// for sharing states
+    fn to_state_string(&self) -> String {
+        format!(
+            "{} {}",
+            self.shape.iter().map(|cut| cut.0).join("x"),
+            self.stickers_to_string()
+        )
+    }
 
-#[derive(Clone, Debug)]
-struct App {
-    puzzle: Puzzle,
-    cam_nd_to_4d: CameraNdTo4d,
-    cam_4d_to_3d: Camera4dTo3d,
-    cam_3d_to_2d: Camera3dTo2d,
-    layout: Layout2d,
-    /// where the labels for the sides go
-    /// the centers if odd and offset in the positive direction if even
-    side_positions: HashMap<Side, Piece>,
-    turn_builder: TurnBuilder,
-    clicked_pieces: HashSet<Piece>,
-    internal_color: Color32,
-    internal_format: StickerFormat,
-    hovered_format: StickerFormatBuilder,
-    clicked_format: StickerFormatBuilder,
-    gripped_format: StickerFormatBuilder,
-    default_filter_format: StickerFormat,
-    default_no_filter_format: StickerFormat,
-    filter_sequence: FilterSequence,
-    filter_stage: Option<usize>,
-    ui_dim: usize,
-    ui_cuts: Vec<i16>,
+    /// whitespace in the stickers is ignored, so they can be wrapped
+    fn from_state_string(text: &str) -> Result<Self, String> {
+        let (shape, names) = text
+            .trim()
+            .split_once(char::is_whitespace)
+            .ok_or("expected a shape and then stickers")?;
+        let shape = Cut::parse_shape(shape).ok_or(format!("bad shape {shape}"))?;
+        let names = names
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>();
+        let stickers = Cut::sticker_count(&shape);
+        if names.chars().count() != stickers {
+            return Err(format!(
+                "expected {stickers} stickers, not {}",
+                names.chars().count()
+            ));
+        }
+        Self::from_stickers_string(&shape, &names).ok_or(format!(
+            "the stickers aren't sides of a {}d puzzle",
+            shape.len()
+        ))
+    }
+
+    fn from_stickers_string(shape: &[Cut], names: &str) -> Option<Self> {
+        let mut names = names.chars();
+        let stickers = Position::all(shape)
+            .filter_map(|pos| Sticker::try_from(shape, pos))
+            .map(|sticker| {
+                let side = Side::try_from_name(names.next()?)
+                    .filter(|side| side.axis().into_usize() < shape.len())?;
+                Some((sticker, side))
+            })
+            .collect::<Option<_>>()?;
+        names.next().is_none().then(|| Puzzle {
+            shape: shape.to_vec(),
+            stickers,
+        })
+    }
 }
-impl App {
-    const MAX_DIM: usize = 10;
-    const MAX_LAYERS: i16 = 19;
+impl Puzzle<Sticker> {
+    /// each sticker is labelled with where it started,
+    /// so turning this tracks where the stickers go
+    #[inline(never)]
+    fn labelled(shape: &[Cut]) -> Self {
+        Puzzle {
+            shape: shape.to_vec(),
+            stickers: Position::all(shape)
+                .filter_map(|pos| Sticker::try_from(shape, pos))
+                .map(|sticker| (sticker.clone(), sticker))
+                .collect(),
+        }
+    }
+}
+impl<T: Clone> Puzzle<T> {
+    // #[inline(never)]
+    // fn turn_side(&mut self, turn: &SideTurn) -> Result<(), TurnError> {
+    //     let SideTurn {
+    //         ref layers,
+    //         side,
+    //         from,
+    //         to,
+    //     } = *turn;
+    //     if side == from.into_side()
+    //         || !side == from.into_side()
+    //         || side == to.into_side()
+    //         || !side == to.into_side()
+    //         || from == to
+    //     {
+    //         return Err(TurnError::UndefinedPlane);
+    //     }
+    //     // assert!(from.0 >= 0 && to.0 >= 0);
+    //     // TODO: i don't think this needs to be a hashmap
+    //     let mut new_stickers = Vec::new();
+    //     let mut from_pos = vec![Coord(0); self.shape.len()].into_boxed_slice();
+    //     for pos in self.stickers.keys() {
+    //         // TODO: layer mask
+    //         // if if side.0 >= 0 {
+    //         //     layers.0[pos[side.0 as usize].0 as usize]
+    //         // } else {
+    //         //     layers.0[pos[(!side.0) as usize].0 as usize]
+    //         // } {
+    //         if if side.is_positive() {
+    //             ((self.shape[side.into_usize()].0 - 1)..=self.shape[side.into_usize()].0)
+    //                 .contains(&pos[side.into_usize()].0)
+    //         } else {
+    //             ((-self.shape[(!side).into_usize()].0)..=(1 - self.shape[(!side).into_usize()].0))
+    //                 .contains(&pos[(!side).into_usize()].0)
+    //         } {
+    //             // TODO: compute to_pos instead of from_pos???
+    //             // let mut from_pos = pos.clone();
+    //             // this is actually faster, i checked
+    //             from_pos.clone_from_slice(pos);
+
+    //             // for cuboids, if you can't turn 90 degrees, just turn 180 degrees
+    //             if self.shape[from.into_usize()] == self.shape[to.into_usize()] {
+    //                 from_pos[from.into_usize()] = pos[to.into_usize()];
+    //                 from_pos[to.into_usize()] = -pos[from.into_usize()];
+    //             } else {
+    //                 from_pos[from.into_usize()] = -pos[from.into_usize()];
+    //                 from_pos[to.into_usize()] = -pos[to.into_usize()];
+    //             }
+    //             new_stickers.push((pos.clone(), self.stickers[&from_pos]));
+    //         }
+    //     }
+    //     self.stickers.extend(new_stickers);
+    //     Ok(())
+    // }
+    #[inline(never)]
+    fn turn_side(&mut self, turn: &SideTurn) -> Result<(), TurnError> {
+        let SideTurn {
+            ref layers,
+            side,
+            from,
+            to,
+        } = *turn;
+        if side == from.into_side()
+            || !side == from.into_side()
+            || side == to.into_side()
+            || !side == to.into_side()
+            || from == to
+        {
+            return Err(TurnError::UndefinedPlane);
+        }
+        let mut new_stickers = Vec::new();
+        let mut from_pos = Sticker(Position(vec![Coord(0); self.shape.len()]));
+        let axis = side.axis().into_usize();
+        let cut = self.shape[axis];
+        if !layers.fits(cut) {
+            return Err(TurnError::LayerOutOfRange {
+                layer: layers.0.len(),
+                layers: cut.0,
+            });
+        }
+        // indexed by slab
+        let mut turned = vec![false; cut.0 as usize + 1];
+        for slab in layers.slabs(&self.shape, side) {
+            turned[slab as usize] = true;
+        }
+        for (pos, old_color) in &self.stickers {
+            // TODO: rename pos to sticker
+            if turned[cut.slab(pos.0.0[axis]) as usize] {
+                // TODO: compute to_pos instead of from_pos???
+
+                // let mut from_pos = pos.clone();
+                // this is actually faster, i checked
+                from_pos.0.0.clone_from_slice(&pos.0.0);
+
+                // for cuboids, if you can't turn 90 degrees, just turn 180 degrees
+                if self.shape[from.into_usize()] == self.shape[to.into_usize()] {
+                    from_pos.0.0[from.into_usize()] = pos.0.0[to.into_usize()];
+                    from_pos.0.0[to.into_usize()] = -pos.0.0[from.into_usize()];
+                } else {
+                    from_pos.0.0[from.into_usize()] = -pos.0.0[from.into_usize()];
+                    from_pos.0.0[to.into_usize()] = -pos.0.0[to.into_usize()];
+                }
+                new_stickers.push((old_color as *const _, self.stickers[&from_pos].clone()));
+            }
+        }
+        for (old_sticker, new_sticker) in new_stickers {
+            // # Safety: old sticker is where the entry was,
+            // and we didn't modify the map so it should still be there.
+            unsafe {
+                *(old_sticker as *mut _) = new_sticker;
+            }
+        }
+        Ok(())
+    }
+
+    #[inline(never)]
+    fn turn_puzzle(&mut self, turn: &PuzzleTurn) -> Result<(), TurnError> {
+        let PuzzleTurn { from, to } = *turn;
+        if from == to {
+            return Err(TurnError::UndefinedPlane);
+        }
+        let mut new_stickers = Vec::new();
+        for pos in self.stickers.keys() {
+            let mut from_pos = pos.clone();
+            // for cuboids, if you can't turn 90 degrees, just turn 180 degrees
+            if self.shape[from.into_usize()] == self.shape[to.into_usize()] {
+                from_pos.0.0[from.into_usize()] = pos.0.0[to.into_usize()];
+                from_pos.0.0[to.into_usize()] = -pos.0.0[from.into_usize()];
+            } else {
+                from_pos.0.0[from.into_usize()] = -pos.0.0[from.into_usize()];
+                from_pos.0.0[to.into_usize()] = -pos.0.0[to.into_usize()];
+            }
+            new_stickers.push((pos.clone(), self.stickers[&from_pos].clone()));
+        }
+        self.stickers = HashMap::from_iter(new_stickers);
+        Ok(())
+    }
+
+    /// whether all the sides and axes of `turn` are on this puzzle
+    fn check_axes(&self, turn: &Turn) -> Result<(), TurnError> {
+        let dim = self.shape.len();
+        let (side, from, to) = match turn {
+            Turn::Side(SideTurn { side, from, to, .. }) => (Some(*side), *from, *to),
+            Turn::Puzzle(PuzzleTurn { from, to }) => (None, *from, *to),
+        };
+        if let Some(side) = side
+            && side.axis().into_usize() >= dim
+        {
+            return Err(TurnError::SideOutOfRange { side, dim });
+        }
+        match [from, to].into_iter().find(|axis| axis.into_usize() >= dim) {
+            Some(axis) => Err(TurnError::AxisOutOfRange { axis, dim }),
+            None => Ok(()),
+        }
+    }
+
+    /// reflects the whole puzzle, swapping the two sides of `axis`
+    fn mirror(&mut self, axis: Axis) {
+        let axis = axis.into_usize();
+        self.stickers = std::mem::take(&mut self.stickers)
+            .into_iter()
+            .map(|(mut sticker, color)| {
+                sticker.0.0[axis] = -sticker.0.0[axis];
+                (sticker, color)
+            })
+            .collect();
+    }
+
+    #[inline(never)]
+    fn turn(&mut self, turn: &Turn) -> Result<(), TurnError> {
+        self.check_axes(turn)?;
+        match turn {
+            Turn::Side(turn) => self.turn_side(turn),
+            Turn::Puzzle(turn) => self.turn_puzzle(turn),
+        }
+    }
+}
+impl Puzzle {
+    /// returns the scramble
+    #[inline(never)]
+    fn scramble(&mut self, rng: &mut impl Rng) -> Vec<Turn> {
+        const SCRAMBLE_N: usize = 1000;
+        let start = std::time::Instant::now();
+        let dim = self.shape.len() as i16;
+        let side_dist = rand::distr::Uniform::new(-dim, dim).unwrap();
+        let axis_dist = rand::distr::Uniform::new(0, dim).unwrap();
+        let mut turns = Vec::with_capacity(SCRAMBLE_N);
+        for _ in 0..SCRAMBLE_N {
+            // TODO: layer mask
+            let side = rng.sample(side_dist);
+            let from = rng.sample(axis_dist);
+            if side == from || !side == from {
+                continue;
+            }
+            let to = rng.sample(axis_dist);
+            if side == to || !side == to || from == to {
+                continue;
+            }
+            turns.push(Turn::Side(SideTurn {
+                layers: LayerMask::new(),
+                side: Side(side),
+                from: Axis::new(from),
+                to: Axis::new(to),
+            }));
+        }
+        let turns = Turn::simplify(&self.shape, &turns);
+        for turn in &turns {
+            self.turn(turn).unwrap();
+        }
+        println!(
+            "scrambled with {} moves in {:?}",
+            Turn::count_moves(&self.shape, &turns),
+            start.elapsed()
+        );
+        turns
+    }
+}
+
+/// `sources[sticker]` is where the sticker that ends up at `sticker` came from.
+/// stickers that don't move aren't included.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct StickerPermutation {
+    shape: Vec<Cut>,
+    sources: HashMap<Sticker, Sticker>,
+}
+/// orbits of pieces, by their piece key, with `true` for the stickers on them
+type OddOrbits = HashSet<(Vec<(Cut, i16)>, bool)>;
+
+impl StickerPermutation {
+    /// the permutation from doing `turns` on a scratch puzzle
+    #[inline(never)]
+    fn new(shape: &[Cut], turns: &[Turn]) -> Result<Self, TurnError> {
+        let mut scratch = Puzzle::labelled(shape);
+        for turn in turns {
+            scratch.turn(turn)?;
+        }
+        Ok(StickerPermutation {
+            shape: shape.to_vec(),
+            sources: scratch
+                .stickers
+                .into_iter()
+                .filter(|(sticker, source)| sticker != source)
+                .collect(),
+        })
+    }
+
+    /// each cycle is in the order the stickers move along it
+    fn cycles(&self) -> Vec<Vec<Sticker>> {
+        let destinations: HashMap<&Sticker, &Sticker> =
+            self.sources.iter().map(|(to, from)| (from, to)).collect();
+        let mut seen = HashSet::new();
+        let mut ret = Vec::new();
+        for start in self.sources.keys() {
+            if seen.contains(start) {
+                continue;
+            }
+            let mut cycle = Vec::new();
+            let mut sticker = start;
+            loop {
+                seen.insert(sticker);
+                cycle.push(sticker.clone());
+                sticker = destinations[sticker];
+                if sticker == start {
+                    break;
+                }
+            }
+            ret.push(cycle);
+        }
+        ret
+    }
+
+    /// `ret[piece]` is where the piece that ends up at `piece` came from,
+    /// for the pieces that get moved or twisted
+    fn piece_sources(&self) -> HashMap<Piece, Piece> {
+        self.sources
+            .iter()
+            .map(|(to, from)| (to.piece(&self.shape), from.piece(&self.shape)))
+            .collect()
+    }
+
+    /// each cycle is in the order the pieces move along it, and pieces twisted in place are 1-cycles.
+    /// the bool is whether the cycle is twisted,
+    /// ie whether the stickers don't come back to where they started after going around once.
+    fn piece_cycles(&self) -> Vec<(Vec<Piece>, bool)> {
+        let sources = self.piece_sources();
+        let destinations: HashMap<&Piece, &Piece> =
+            sources.iter().map(|(to, from)| (from, to)).collect();
+        let sticker_cycle_lens: HashMap<Sticker, usize> = self
+            .cycles()
+            .into_iter()
+            .flat_map(|cycle| {
+                let len = cycle.len();
+                cycle.into_iter().map(move |sticker| (sticker, len))
+            })
+            .collect();
+        let mut piece_sticker_cycle_lens: HashMap<Piece, Vec<usize>> = HashMap::new();
+        for (sticker, len) in &sticker_cycle_lens {
+            piece_sticker_cycle_lens
+                .entry(sticker.piece(&self.shape))
+                .or_default()
+                .push(*len);
+        }
+        let mut seen = HashSet::new();
+        let mut ret = Vec::new();
+        for start in sources.keys() {
+            if seen.contains(start) {
+                continue;
+            }
+            let mut cycle = Vec::new();
+            let mut piece = start;
+            loop {
+                seen.insert(piece);
+                cycle.push(piece.clone());
+                piece = destinations[piece];
+                if piece == start {
+                    break;
+                }
+            }
+            let twisted = cycle
+                .iter()
+                .flat_map(|piece| &piece_sticker_cycle_lens[piece])
+                .any(|&len| len != cycle.len());
+            ret.push((cycle, twisted));
+        }
+        ret
+    }
+
+    /// eg "2 3-cycles of corners, 1 twisted edge"
+    fn cycle_structure(&self) -> String {
+        let mut counts: HashMap<(usize, usize, bool), usize> = HashMap::new();
+        for (cycle, twisted) in self.piece_cycles() {
+            let kind = cycle[0].sides(&self.shape).count();
+            *counts.entry((kind, cycle.len(), twisted)).or_default() += 1;
+        }
+        if counts.is_empty() {
+            return "nothing moves".into();
+        }
+        counts
+            .into_iter()
+            .sorted_by_key(|&((kind, len, twisted), _count)| {
+                (std::cmp::Reverse(kind), std::cmp::Reverse(len), twisted)
+            })
+            .map(|((kind, len, twisted), count)| {
+                let kind = Piece::kind_name(self.shape.len(), kind);
+                if len == 1 {
+                    format!(
+                        "{count} twisted {kind}{}",
+                        if count == 1 { "" } else { "s" }
+                    )
+                } else {
+                    format!(
+                        "{count} {}{len}-cycle{} of {kind}s",
+                        if twisted { "twisted " } else { "" },
+                        if count == 1 { "" } else { "s" },
+                    )
+                }
+            })
+            .join(", ")
+    }
+
+    /// how many times it has to be repeated to do nothing,
+    /// or None if that doesn't fit in a u64
+    fn order(&self) -> Option<u64> {
+        self.cycles()
+            .iter()
+            .try_fold(1, |order, cycle| lcm(order, cycle.len() as u64))
+    }
+
+    /// how many times it has to be repeated to look solved again when starting solved,
+    /// which can be less than the order when stickers of the same color get swapped
+    fn solved_order(&self) -> Option<u64> {
+        self.cycles().iter().try_fold(1, |order, cycle| {
+            let colors = cycle
+                .iter()
+                .map(|sticker| sticker.side(&self.shape))
+                .collect::<Vec<_>>();
+            let period = (1..=colors.len())
+                .filter(|period| colors.len() % period == 0)
+                .find(|period| {
+                    (0..colors.len()).all(|i| colors[i] == colors[(i + period) % colors.len()])
+                })
+                .unwrap();
+            lcm(order, period as u64)
+        })
+    }
+
+    /// for each orbit of pieces that gets moved, its name and whether it gets an odd permutation
+    fn orbit_parities(&self) -> Vec<(String, bool)> {
+        let mut parities: HashMap<Vec<(Cut, i16)>, bool> = HashMap::new();
+        for (cycle, _twisted) in self.piece_cycles() {
+            *parities.entry(cycle[0].orbit(&self.shape)).or_default() ^= cycle.len() % 2 == 0;
+        }
+        let names = Piece::orbit_names(&self.shape);
+        parities
+            .into_iter()
+            .map(|(orbit, parity)| (names[&orbit].clone(), parity))
+            .sorted()
+            .collect()
+    }
+
+    /// the orbits where the pieces get an odd permutation, with `false`,
+    /// and where the stickers on the pieces do, with `true`
+    fn odd_orbits(&self) -> OddOrbits {
+        let mut ret = HashSet::new();
+        let piece_orbits = self
+            .piece_cycles()
+            .into_iter()
+            .filter(|(cycle, _twisted)| cycle.len() % 2 == 0)
+            .map(|(cycle, _twisted)| (cycle[0].orbit(&self.shape), false));
+        let sticker_orbits = self
+            .cycles()
+            .into_iter()
+            .filter(|cycle| cycle.len() % 2 == 0)
+            .map(|cycle| (cycle[0].piece(&self.shape).orbit(&self.shape), true));
+        for orbit in piece_orbits.chain(sticker_orbits) {
+            if !ret.remove(&orbit) {
+                ret.insert(orbit);
+            }
+        }
+        ret
+    }
+}
+
+impl Puzzle {
+    /// the side each sticker of each piece is on, and its color
+    fn piece_stickers(&self) -> HashMap<Piece, Vec<(Side, Side)>> {
+        let mut pieces: HashMap<Piece, Vec<(Side, Side)>> = HashMap::new();
+        for (sticker, &color) in &self.stickers {
+            pieces
+                .entry(sticker.piece(&self.shape))
+                .or_default()
+                .push((sticker.side(&self.shape), color));
+        }
+        pieces
+    }
+
+    /// whether a piece with `stickers` is the mirror image of the solved piece with its colors,
+    /// or None if it doesn't have a sticker for every axis, so turning can mirror it
+    fn is_mirror_image(shape: &[Cut], stickers: &[(Side, Side)]) -> Option<bool> {
+        if stickers.len() != shape.len()
+            || !stickers.iter().map(|(side, _)| side.axis()).all_unique()
+            || !stickers.iter().map(|(_, color)| color.axis()).all_unique()
+        {
+            return None;
+        }
+        // the sign of the signed permutation taking each color to where it is
+        let inversions = stickers
+            .iter()
+            .sorted_by_key(|(side, _)| side.axis())
+            .map(|(_, color)| color.axis())
+            .tuple_combinations()
+            .filter(|(a, b)| a > b)
+            .count();
+        let flips = stickers
+            .iter()
+            .filter(|(side, color)| side.is_positive() != color.is_positive())
+            .count();
+        Some((inversions + flips) % 2 == 1)
+    }
+
+    /// whether most pieces are mirror images, like after `mirror`,
+    /// so the stickers are solved towards the mirrored color scheme
+    fn is_mirrored(&self) -> bool {
+        let (mirrored, pieces) = self
+            .piece_stickers()
+            .values()
+            .filter_map(|stickers| Self::is_mirror_image(&self.shape, stickers))
+            .fold((0, 0), |(mirrored, pieces), m| {
+                (mirrored + m as usize, pieces + 1)
+            });
+        2 * mirrored > pieces
+    }
+
+    /// why the stickers can't be turned back to solved, if they can't.
+    /// checks the color counts, that every piece is a real piece and not its mirror image,
+    /// the permutation parity of each orbit, and in 3d, the corner twists.
+    /// other twists in 4d and up aren't checked.
+    /// a mirrored color scheme is checked mirrored back, since either can be solved.
+    fn problems(&self) -> Vec<String> {
+        if self.is_mirrored() {
+            let mut puzzle = self.clone();
+            puzzle.mirror(Axis::new(0));
+            return puzzle.problems_mirrored(Some(Axis::new(0)));
+        }
+        self.problems_mirrored(None)
+    }
+
+    /// the problems of a puzzle that was mirrored across `mirrored`, naming pieces where they were
+    #[inline(never)]
+    fn problems_mirrored(&self, mirrored: Option<Axis>) -> Vec<String> {
+        let shape = &self.shape;
+        let mut problems = Vec::new();
+
+        let solved = Puzzle::new(shape);
+        let counts = self.stickers.values().copied().counts();
+        for (side, solved_count) in solved.stickers.values().copied().counts() {
+            let count = counts.get(&side).copied().unwrap_or(0);
+            if count != solved_count {
+                problems.push(format!(
+                    "{} has {count} stickers, not {solved_count}",
+                    side.name()
+                ));
+            }
+        }
+        if !problems.is_empty() {
+            problems.sort();
+            return problems;
+        }
+
+        let pieces = self.piece_stickers();
+        let key = |piece: &Piece, colors: &mut dyn Iterator<Item = Side>| {
+            (piece.orbit(shape), colors.sorted().collect::<Vec<_>>())
+        };
+        let painted_key = |piece: &Piece, stickers: &[(Side, Side)]| {
+            key(piece, &mut stickers.iter().map(|s| s.1))
+        };
+        // the solved pieces of each orbit, by their colors
+        let mut solved_pieces: HashMap<_, Vec<Piece>> = HashMap::new();
+        for piece in pieces.keys() {
+            solved_pieces
+                .entry(key(piece, &mut piece.sides(shape)))
+                .or_default()
+                .push(piece.clone());
+        }
+
+        let names = Piece::orbit_names(shape);
+        let painted_counts = pieces
+            .iter()
+            .map(|(piece, stickers)| painted_key(piece, stickers))
+            .counts();
+        for ((orbit, colors), count) in painted_counts {
+            let solved_count = solved_pieces
+                .get(&(orbit.clone(), colors.clone()))
+                .map_or(0, Vec::len);
+            let colors = colors.iter().map(|side| side.name()).collect::<String>();
+            let kind = &names[&orbit];
+            if solved_count == 0 {
+                problems.push(format!("none of the {kind} are colored {colors}"));
+            } else if count > solved_count {
+                problems.push(format!(
+                    "{count} of the {kind} are colored {colors}, not {solved_count}"
+                ));
+            }
+        }
+        for (piece, stickers) in &pieces {
+            let mut unmirrored = piece.clone();
+            if let Some(axis) = mirrored {
+                unmirrored.0.0[axis.into_usize()] = -unmirrored.0.0[axis.into_usize()];
+            }
+            let name = unmirrored.name(shape);
+            if let Some((side, color)) = stickers.iter().find(|(side, color)| {
+                shape[side.axis().into_usize()] != shape[color.axis().into_usize()]
+            }) {
+                problems.push(format!(
+                    "the {name} piece has {} on its {} side, which is a different size",
+                    color.name(),
+                    side.name()
+                ));
+                continue;
+            }
+            // pieces with a sticker for every axis can't be turned into their mirror image
+            if Self::is_mirror_image(shape, stickers) == Some(true) {
+                problems.push(format!("the {name} piece is a mirror image"));
+            }
+        }
+        if !problems.is_empty() {
+            problems.sort();
+            return problems;
+        }
+
+        if shape.len() == 3 {
+            // how many times each corner is twisted from having its U or D color on U or D,
+            // counting the same way around every corner
+            let twist = pieces
+                .values()
+                .filter(|stickers| {
+                    stickers.len() == 3 && stickers.iter().map(|(side, _)| side.axis()).all_unique()
+                })
+                .filter_map(|stickers| {
+                    let (side, _) = stickers
+                        .iter()
+                        .find(|(_, color)| color.axis() == Axis::new(1))?;
+                    let axis = side.axis().into_usize();
+                    let negatives = stickers.iter().filter(|(side, _)| !side.is_positive());
+                    Some(if negatives.count() % 2 == 0 {
+                        (axis + 2) % 3
+                    } else {
+                        (4 - axis) % 3
+                    })
+                })
+                .sum::<usize>();
+            if twist % 3 != 0 {
+                problems.push("a corner is twisted, which turning can't fix".into());
+                return problems;
+            }
+        }
+
+        // where each sticker came from, picking any of the pieces with the same colors
+        let sticker_at = |piece: &Piece, side: Side| {
+            let mut coords = piece.0.0.clone();
+            let axis = side.axis().into_usize();
+            coords[axis] = Coord(if side.is_positive() {
+                shape[axis].0
+            } else {
+                -shape[axis].0
+            });
+            Sticker(Position(coords))
+        };
+        let mut unused = solved_pieces.clone();
+        let mut sources = HashMap::new();
+        // the orbits where two pieces look the same, so swapping them changes nothing
+        let mut swappable = HashMap::new();
+        for (piece, stickers) in &pieces {
+            let key = painted_key(piece, stickers);
+            if solved_pieces[&key].len() > 1 {
+                swappable.insert(key.0.clone(), stickers.len());
+            }
+            let from = unused.get_mut(&key).unwrap().pop().unwrap();
+            for &(side, color) in stickers {
+                let (to, from) = (sticker_at(piece, side), sticker_at(&from, color));
+                if to != from {
+                    sources.insert(to, from);
+                }
+            }
+        }
+        let parity = StickerPermutation {
+            shape: shape.clone(),
+            sources,
+        }
+        .odd_orbits();
+
+        // the parities turns can make, as a basis over GF(2),
+        // where adding is symmetric difference and each vector has a different largest entry
+        let dim = shape.len();
+        let mut turns = Vec::new();
+        for (from, to) in (0..dim).tuple_combinations() {
+            let (from, to) = (Axis::from_usize(from), Axis::from_usize(to));
+            turns.push(Turn::Puzzle(PuzzleTurn { from, to }));
+            for axis in
+                (0..dim).filter(|&axis| axis != from.into_usize() && axis != to.into_usize())
+            {
+                for layer in 1..=shape[axis].0 as usize {
+                    let mut layers = LayerMask(vec![]);
+                    layers.set(layer, true);
+                    turns.push(Turn::Side(SideTurn {
+                        layers,
+                        side: Side::new(axis as i16),
+                        from,
+                        to,
+                    }));
+                }
+            }
+        }
+        let swap = |orbit: &Vec<(Cut, i16)>, stickers: usize| {
+            let mut swap = HashSet::from_iter([(orbit.clone(), false)]);
+            if stickers % 2 == 1 {
+                swap.insert((orbit.clone(), true));
+            }
+            swap
+        };
+        let swaps = swappable
+            .iter()
+            .map(|(orbit, &stickers)| swap(orbit, stickers));
+        let reduce = |basis: &[OddOrbits], mut v: OddOrbits| loop {
+            let Some(largest) = v.iter().max().cloned() else {
+                return v;
+            };
+            match basis.iter().find(|b| b.iter().max() == Some(&largest)) {
+                Some(b) => v = v.symmetric_difference(b).cloned().collect(),
+                None => return v,
+            }
+        };
+        let mut basis = Vec::new();
+        for v in turns
+            .iter()
+            .map(|turn| StickerPermutation::new(shape, std::slice::from_ref(turn)).unwrap())
+            .map(|permutation| permutation.odd_orbits())
+            .chain(swaps)
+        {
+            let v = reduce(&basis, v);
+            if !v.is_empty() {
+                basis.push(v);
+            }
+        }
+        let parity = reduce(&basis, parity);
+        if parity.is_empty() {
+            return problems;
+        }
+        // lots of swaps and flips are the same up to turning, so say the simplest one that'd fix it,
+        // trying the pieces with the most stickers first
+        let orbits = pieces
+            .iter()
+            .map(|(piece, stickers)| (stickers.len(), piece.orbit(shape)))
+            .unique()
+            .sorted_by(|a, b| b.0.cmp(&a.0).then_with(|| names[&a.1].cmp(&names[&b.1])))
+            .collect_vec();
+        let fixes = |v: &HashSet<_>| {
+            reduce(&basis, parity.symmetric_difference(v).cloned().collect()).is_empty()
+        };
+        let swapped = orbits
+            .iter()
+            .find(|(stickers, orbit)| fixes(&swap(orbit, *stickers)));
+        let flipped = orbits
+            .iter()
+            .find(|(_, orbit)| fixes(&HashSet::from_iter([(orbit.clone(), true)])));
+        problems.push(match (swapped, flipped) {
+            (Some((_, orbit)), _) => format!(
+                "the pieces are swapped in a way turning can't fix, like two of the {}",
+                names[orbit]
+            ),
+            (None, Some((_, orbit))) => format!(
+                "the pieces are flipped in a way turning can't fix, like one of the {}",
+                names[orbit]
+            ),
+            (None, None) => "the pieces are arranged in a way turning can't fix".into(),
+        });
+        problems
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+fn lcm(a: u64, b: u64) -> Option<u64> {
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+/// the quarter turns of a puzzle as permutations of its stickers,
+/// for searching without a `HashMap` per state.
+/// a state is the color of each sticker in `stickers`.
+#[derive(Clone, Debug)]
+struct MoveTable {
+    shape: Vec<Cut>,
+    /// the order of the stickers in a state
+    stickers: Vec<Sticker>,
+    /// the sticker indices on each side, for checking if a state is solved
+    sides: Vec<Vec<usize>>,
+    /// the turn and `sources[i]`, the index that ends up at `i`
+    moves: Vec<(Turn, Vec<usize>)>,
+    /// `inverses[m]` undoes `moves[m]`
+    inverses: Vec<usize>,
+    /// `commutes[m][n]` if doing `moves[m]` and `moves[n]` in either order is the same
+    commutes: Vec<Vec<bool>>,
+}
+impl MoveTable {
+    /// every quarter turn of the outer layers, skipping ones that do the same thing
+    #[inline(never)]
+    fn new(shape: &[Cut], puzzle_turns: bool) -> Result<Self, String> {
+        let labelled = Puzzle::labelled(shape);
+        let stickers = labelled
+            .stickers
+            .keys()
+            .cloned()
+            .sorted_by_key(|sticker| sticker.0.0.iter().map(|c| c.0).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let index = stickers
+            .iter()
+            .enumerate()
+            .map(|(i, sticker)| (sticker.clone(), i))
+            .collect::<HashMap<_, _>>();
+        let dim = shape.len() as i16;
+        let sides = (-dim..dim)
+            .map(|side| {
+                stickers
+                    .iter()
+                    .positions(|sticker| sticker.side(shape) == Side::new(side))
+                    .collect()
+            })
+            .collect();
+
+        let mut turns = Vec::new();
+        for from in 0..dim {
+            for to in 0..dim {
+                if from == to {
+                    continue;
+                }
+                let (from, to) = (Axis::new(from), Axis::new(to));
+                for side in -dim..dim {
+                    let side = Side::new(side);
+                    if side.axis() != from && side.axis() != to {
+                        turns.push(Turn::Side(SideTurn {
+                            layers: LayerMask::new(),
+                            side,
+                            from,
+                            to,
+                        }));
+                    }
+                }
+                if puzzle_turns {
+                    turns.push(Turn::Puzzle(PuzzleTurn { from, to }));
+                }
+            }
+        }
+        let identity = (0..stickers.len()).collect::<Vec<_>>();
+        let mut moves: Vec<(Turn, Vec<usize>)> = Vec::new();
+        for turn in turns {
+            let mut scratch = labelled.clone();
+            scratch
+                .turn(&turn)
+                .map_err(|err| format!("can't do {turn}: {err}"))?;
+            let sources = stickers
+                .iter()
+                .map(|sticker| index[&scratch.stickers[sticker]])
+                .collect::<Vec<_>>();
+            if sources != identity && moves.iter().all(|(_, other)| *other != sources) {
+                moves.push((turn, sources));
+            }
+        }
+        let compose = |a: &[usize], b: &[usize]| b.iter().map(|&i| a[i]).collect::<Vec<_>>();
+        let inverses = moves
+            .iter()
+            .map(|(_, a)| {
+                moves
+                    .iter()
+                    .position(|(_, b)| compose(a, b) == identity)
+                    .expect("the inverse of a move should be a move")
+            })
+            .collect();
+        let commutes = moves
+            .iter()
+            .map(|(_, a)| {
+                moves
+                    .iter()
+                    .map(|(_, b)| compose(a, b) == compose(b, a))
+                    .collect()
+            })
+            .collect();
+        Ok(MoveTable {
+            shape: shape.to_vec(),
+            stickers,
+            sides,
+            moves,
+            inverses,
+            commutes,
+        })
+    }
+
+    fn color(&self, side: Side) -> u8 {
+        (side.0 + self.shape.len() as i16) as u8
+    }
+
+    fn state(&self, puzzle: &Puzzle) -> Box<[u8]> {
+        self.stickers
+            .iter()
+            .map(|sticker| self.color(puzzle.stickers[sticker]))
+            .collect()
+    }
+
+    fn apply(&self, state: &[u8], m: usize) -> Box<[u8]> {
+        self.moves[m].1.iter().map(|&i| state[i]).collect()
+    }
+
+    fn is_solved(&self, state: &[u8]) -> bool {
+        self.sides
+            .iter()
+            .all(|side| side.iter().map(|&i| state[i]).all_equal())
+    }
+
+    /// whether doing `m` after `path` can't lead anywhere shorter paths don't
+    fn is_redundant(&self, path: &[usize], m: usize) -> bool {
+        let Some(&last) = path.last() else {
+            return false;
+        };
+        // don't undo the last move, or do commuting moves in both orders
+        if self.inverses[last] == m || (self.commutes[last][m] && m < last) {
+            return true;
+        }
+        // three quarter turns is one quarter turn the other way
+        m == last && path.len() >= 2 && path[path.len() - 2] == m
+    }
+}
+
+/// the turns that solve a puzzle, or why there aren't any
+type Solution = Result<Vec<Turn>, String>;
+
+/// searches for short solutions with IDA*,
+/// for puzzles small enough to keep every state within a few moves of solved
+#[derive(Clone, Debug)]
+struct Solver {
+    moves: MoveTable,
+    /// the number of moves to solve every state within `depth` moves of solved.
+    /// states further away aren't in the table.
+    table: HashMap<Box<[u8]>, u8>,
+    depth: u8,
+}
+impl Solver {
+    const MAX_STICKERS: usize = 128;
+    const MAX_TABLE_STATES: usize = 1 << 20;
+    const MAX_DEPTH: usize = 30;
+    const MAX_NODES: usize = 20_000_000;
+
+    #[inline(never)]
+    fn new(shape: &[Cut]) -> Result<Self, String> {
+        let start = std::time::Instant::now();
+        let stickers = Cut::sticker_count(shape);
+        if stickers > Self::MAX_STICKERS {
+            return Err(format!(
+                "too big to solve, {stickers} stickers is more than {}",
+                Self::MAX_STICKERS
+            ));
+        }
+        // every rotation of solved starts in the table
+        if Symmetry::rotation_count(shape).is_none_or(|n| n > Self::MAX_TABLE_STATES) {
+            return Err("too big to solve, it has too many rotations".into());
+        }
+        let mut solver = Solver {
+            moves: MoveTable::new(shape, true)?,
+            table: HashMap::new(),
+            depth: 0,
+        };
+
+        // breadth first search out from every orientation of the solved puzzle
+        let mut frontier = Vec::new();
+        for symmetry in Symmetry::rotations(shape) {
+            if solver.table.len() >= Self::MAX_TABLE_STATES {
+                break;
+            }
+            let state = solver
+                .moves
+                .stickers
+                .iter()
+                .map(|sticker| solver.moves.color(symmetry.side(sticker.side(shape))))
+                .collect::<Box<[u8]>>();
+            if solver.table.insert(state.clone(), 0).is_none() {
+                frontier.push(state);
+            }
+        }
+        'bfs: while !frontier.is_empty() && solver.depth < u8::MAX - 1 {
+            let mut next = Vec::new();
+            for state in &frontier {
+                for m in 0..solver.moves.moves.len() {
+                    if solver.table.len() >= Self::MAX_TABLE_STATES {
+                        break 'bfs;
+                    }
+                    let state = solver.moves.apply(state, m);
+                    if !solver.table.contains_key(&state) {
+                        solver.table.insert(state.clone(), solver.depth + 1);
+                        next.push(state);
+                    }
+                }
+            }
+            frontier = next;
+            solver.depth += 1;
+        }
+        println!(
+            "solver gen with {} moves and {} states to depth {} in {:?}",
+            solver.moves.moves.len(),
+            solver.table.len(),
+            solver.depth,
+            start.elapsed()
+        );
+        Ok(solver)
+    }
+
+    /// a lower bound on the number of moves to solve `state`
+    fn heuristic(&self, state: &[u8]) -> usize {
+        self.table
+            .get(state)
+            .map_or(self.depth as usize + 1, |&depth| depth as usize)
+    }
+
+    /// a shortest solution counting every quarter turn and puzzle turn,
+    /// as long as it doesn't take too long to find
+    #[inline(never)]
+    fn solve(&self, puzzle: &Puzzle) -> Solution {
+        if puzzle.shape != self.moves.shape {
+            return Err("the solver is for a different shape".into());
+        }
+        // solve the reflection towards the usual color scheme, then reflect the turns back
+        if puzzle.is_mirrored() {
+            let mut mirrored = puzzle.clone();
+            mirrored.mirror(Axis::new(0));
+            let symmetry = Symmetry::mirror(puzzle.shape.len(), Axis::new(0));
+            return self
+                .solve(&mirrored)
+                .map(|turns| turns.iter().map(|turn| symmetry.turn(turn)).collect());
+        }
+        let start = std::time::Instant::now();
+        let state = self.moves.state(puzzle);
+        let mut path = Vec::new();
+        let mut nodes = 0;
+        for bound in self.heuristic(&state)..=Self::MAX_DEPTH {
+            if self.search(&state, bound, &mut path, &mut nodes) {
+                println!(
+                    "solved in {} turns after {nodes} nodes in {:?}",
+                    path.len(),
+                    start.elapsed()
+                );
+                return Ok(path
+                    .into_iter()
+                    .map(|m| self.moves.moves[m].0.clone())
+                    .collect());
+            }
+            if nodes > Self::MAX_NODES {
+                return Err(format!(
+                    "gave up after {nodes} nodes, looking for {bound} turns"
+                ));
+            }
+        }
+        Err(format!("no solution in {} turns", Self::MAX_DEPTH))
+    }
+
+    /// depth first search for a solution with `path.len() <= bound`
+    fn search(&self, state: &[u8], bound: usize, path: &mut Vec<usize>, nodes: &mut usize) -> bool {
+        *nodes += 1;
+        if self.moves.is_solved(state) {
+            return true;
+        }
+        if path.len() + self.heuristic(state) > bound || *nodes > Self::MAX_NODES {
+            return false;
+        }
+        if let Some(&depth) = self.table.get(state) {
+            // the table knows the way from here
+            let mut state = Box::from(state);
+            for depth in (0..depth).rev() {
+                let m = (0..self.moves.moves.len())
+                    .find(|&m| self.table.get(&self.moves.apply(&state, m)) == Some(&depth))
+                    .expect("a state in the table should have a neighbour closer to solved");
+                state = self.moves.apply(&state, m);
+                path.push(m);
+            }
+            return true;
+        }
+        for m in 0..self.moves.moves.len() {
+            if self.moves.is_redundant(path, m) {
+                continue;
+            }
+            path.push(m);
+            if self.search(&self.moves.apply(state, m), bound, path, nodes) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+}
+
+/// suggested turns toward solving the pieces in a filter
+#[derive(Clone, Debug)]
+struct Hint {
+    turns: Vec<Turn>,
+    /// the pieces the first turn moves
+    moved_pieces: HashSet<Piece>,
+    /// how many of the pieces in the filter are solved now and after the turns
+    solved: (usize, usize),
+    total: usize,
+}
+impl Hint {
+    const MAX_DEPTH: usize = 3;
+    /// how many sticker moves a search can take, counting every sequence of turns
+    const MAX_WORK: usize = 1 << 31;
+
+    /// searches every sequence of up to `MAX_DEPTH` outer layer turns
+    /// for the one that solves the most pieces in `filter`.
+    /// solved means each side matching the color most of its stickers are.
+    /// gives up once `cancelled` is set.
+    #[inline(never)]
+    fn new(puzzle: &Puzzle, filter: &Filter, cancelled: &AtomicBool) -> Result<Self, String> {
+        let shape = &puzzle.shape;
+        // every side turn from one axis to another, before dropping the ones that do the same
+        let dim = shape.len();
+        let turns = dim * dim.saturating_sub(1) * (2 * dim).saturating_sub(4);
+        let work = turns
+            .checked_pow(Self::MAX_DEPTH as u32)
+            .and_then(|n| n.checked_mul(Cut::sticker_count(shape)));
+        if work.is_none_or(|work| work > Self::MAX_WORK) {
+            return Err(format!(
+                "too big for hints, looking {} turns ahead would take too long",
+                Self::MAX_DEPTH
+            ));
+        }
+        let start = std::time::Instant::now();
+        let moves = MoveTable::new(shape, false)?;
+        let state = moves.state(puzzle);
+
+        // the color most of the stickers on each side are
+        let targets = puzzle
+            .stickers
+            .iter()
+            .map(|(sticker, &color)| (sticker.side(shape), color))
+            .into_group_map()
+            .into_iter()
+            .map(|(side, colors)| {
+                let counts = colors.into_iter().counts();
+                let color = counts.into_iter().max_by_key(|&(color, n)| (n, color));
+                (side, color.unwrap().0)
+            })
+            .collect::<HashMap<_, _>>();
+        // the sticker indices and the color they should be for each piece in the filter
+        let pieces = moves
+            .stickers
+            .iter()
+            .enumerate()
+            .filter(|(_, sticker)| filter.selects(shape, &sticker.piece(shape)))
+            .map(|(i, sticker)| {
+                (
+                    sticker.piece(shape),
+                    (i, moves.color(targets[&sticker.side(shape)])),
+                )
+            })
+            .into_group_map()
+            .into_values()
+            .collect::<Vec<_>>();
+        if pieces.is_empty() {
+            return Err("no pieces match the filter".into());
+        }
+        let solved = |state: &[u8]| {
+            pieces
+                .iter()
+                .filter(|piece| piece.iter().all(|&(i, color)| state[i] == color))
+                .count()
+        };
+        let now = solved(&state);
+        if now == pieces.len() {
+            return Err("already solved".into());
+        }
+
+        fn search(
+            moves: &MoveTable,
+            solved: &impl Fn(&[u8]) -> usize,
+            cancelled: &AtomicBool,
+            state: &[u8],
+            path: &mut Vec<usize>,
+            best: &mut (usize, Vec<usize>),
+        ) {
+            if cancelled.load(std::sync::atomic::Ordering::Relaxed) {
+                return;
+            }
+            let score = solved(state);
+            if score > best.0 || (score == best.0 && path.len() < best.1.len()) {
+                *best = (score, path.clone());
+            }
+            if path.len() == Hint::MAX_DEPTH {
+                return;
+            }
+            for m in 0..moves.moves.len() {
+                if moves.is_redundant(path, m) {
+                    continue;
+                }
+                path.push(m);
+                search(moves, solved, cancelled, &moves.apply(state, m), path, best);
+                path.pop();
+            }
+        }
+        let mut best = (now, Vec::new());
+        search(
+            &moves,
+            &solved,
+            cancelled,
+            &state,
+            &mut Vec::new(),
+            &mut best,
+        );
+        println!("hint search in {:?}", start.elapsed());
+        if cancelled.load(std::sync::atomic::Ordering::Relaxed) {
+            return Err("cancelled".into());
+        }
+        if best.1.is_empty() {
+            return Err(format!("no progress within {} turns", Self::MAX_DEPTH));
+        }
+        let turns = best
+            .1
+            .into_iter()
+            .map(|m| moves.moves[m].0.clone())
+            .collect::<Vec<_>>();
+        let moved_pieces = StickerPermutation::new(shape, &turns[..1])
+            .map_err(|err| format!("{err:?}"))?
+            .piece_sources()
+            .into_keys()
+            .collect();
+        Ok(Hint {
+            turns,
+            moved_pieces,
+            solved: (now, best.0),
+            total: pieces.len(),
+        })
+    }
+}
+
+/// a slow computation on another thread, so the ui doesn't freeze while it runs
+#[derive(Clone, Debug)]
+struct Job<T> {
+    result: std::sync::Arc<std::sync::OnceLock<T>>,
+    /// set to ask the computation to stop early
+    cancelled: std::sync::Arc<AtomicBool>,
+}
+impl<T: Send + Sync + 'static> Job<T> {
+    /// `f` can check whether it's been cancelled
+    fn spawn(f: impl FnOnce(&AtomicBool) -> T + Send + 'static) -> Self {
+        let ret = Self::new(Default::default());
+        let (result, cancelled) = (ret.result.clone(), ret.cancelled.clone());
+        std::thread::spawn(move || {
+            let _ = result.set(f(&cancelled));
+        });
+        ret
+    }
+
+    fn new(result: std::sync::OnceLock<T>) -> Self {
+        Job {
+            result: std::sync::Arc::new(result),
+            cancelled: Default::default(),
+        }
+    }
+
+    /// already finished
+    fn done(result: T) -> Self {
+        Self::new(std::sync::OnceLock::from(result))
+    }
+
+    /// the result, once it's finished
+    fn get(&self) -> Option<&T> {
+        self.result.get()
+    }
+
+    fn cancel(&self) {
+        self.cancelled
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// mapping from Pos to (x, y) coordinates
+/// +x is right, +y is up
+#[derive(Clone, Debug)]
+struct Layout2dBuilder {
+    width: usize,
+    height: usize,
+    mapping: Vec<(Vec<Coord>, (usize, usize))>,
+}
+impl Layout2dBuilder {
+    #[inline(never)]
+    fn new(shape: &[Cut]) -> Self {
+        if shape.is_empty() {
+            return Self {
+                width: 1,
+                height: 1,
+                mapping: vec![(Vec::new(), (0, 0))],
+            };
+        }
+        // TODO: refactor to use Position
+        let horizontal = shape.len() % 2 == 1;
+        let lower = Self::new(&shape[..shape.len() - 1]);
+        let last = *shape.last().unwrap();
+        assert_eq!(last.coords().count(), last.0 as usize + 2);
+        let mut ret = Self {
+            width: 0,
+            height: 0,
+            mapping: Vec::with_capacity(lower.mapping.len() * (last.0 as usize + 2)),
+        };
+        for (i, new_coord) in last.coords().enumerate() {
+            let mut lower = lower.clone();
+            lower.mapping = lower
+                .mapping
+                .into_iter()
+                .map(|(mut pos, xy)| {
+                    pos.push(new_coord);
+                    (pos, xy)
+                })
+                .collect();
+            if new_coord.0.abs() == last.0 {
+                // only keep valid positions
+                // we only need to check this if it's a cap
+                lower.mapping.retain(|(pos, _xy)| {
+                    pos.iter()
+                        .zip(shape)
+                        .filter(|(coord, cut)| coord.0.abs() == cut.0)
+                        .count()
+                        <= 1
+                });
+                // TODO: possibly shrink margins
+            } else {
+                debug_assert!(lower.mapping.iter().all(|(pos, _xy)| {
+                    pos.iter()
+                        .zip(shape)
+                        .filter(|(coord, cut)| coord.0.abs() == cut.0)
+                        .count()
+                        <= 1
+                }));
+            }
+            let shift = if horizontal {
+                lower.width
+            } else {
+                lower.height
+            };
+            let shift = if shape.len() > 2 {
+                (shift + 1) * i
+            } else {
+                shift * i
+            };
+            if horizontal {
+                lower.right(shift);
+            } else {
+                lower.down(shift);
+            }
+            ret.union(lower);
+        }
+        assert!(ret.mapping.len() <= lower.mapping.len() * (last.0 as usize + 2));
+        ret
+    }
+
+    #[inline(never)]
+    fn union(&mut self, other: Self) {
+        self.width = self.width.max(other.width);
+        self.height = self.height.max(other.height);
+        let self_len = self.mapping.len();
+        let other_len = other.mapping.len();
+        self.mapping.extend(other.mapping);
+        assert_eq!(self.mapping.len(), self_len + other_len);
+    }
+
+    #[inline(never)]
+    fn right(&mut self, shift: usize) {
+        self.mapping.iter_mut().for_each(|(_pos, (x, _y))| {
+            *x += shift;
+        });
+        self.width += shift;
+    }
+
+    #[inline(never)]
+    fn down(&mut self, shift: usize) {
+        self.mapping.iter_mut().for_each(|(_pos, (_x, y))| {
+            *y += shift;
+        });
+        self.height += shift;
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Layout2d {
+    width: usize,
+    height: usize,
+    mapping: HashMap<Position, (usize, usize)>,
+    inverse: HashMap<(usize, usize), Position>,
+}
+impl Layout2d {
+    #[inline(never)]
+    fn new(shape: &[Cut]) -> Self {
+        let mapping = Layout2dBuilder::new(shape).mapping;
+        // shrink the margins
+        let x_min = mapping.iter().map(|(_pos, (x, _y))| *x).min().unwrap();
+        let y_min = mapping.iter().map(|(_pos, (_x, y))| *y).min().unwrap();
+        let x_max = mapping.iter().map(|(_pos, (x, _y))| *x).max().unwrap();
+        let y_max = mapping.iter().map(|(_pos, (_x, y))| *y).max().unwrap();
+        Layout2d {
+            width: x_max - x_min + 1,
+            height: y_max - y_min + 1,
+            mapping: mapping
+                .clone()
+                .into_iter()
+                .map(|(pos, (x, y))| (Position::new(shape, pos), (x - x_min, y - y_min)))
+                .collect(),
+            inverse: mapping
+                .into_iter()
+                .map(|(pos, (x, y))| ((x - x_min, y - y_min), Position::new(shape, pos)))
+                .collect(),
+        }
+    }
+}
+
+// /// maps a nd point to a (n-1)d point
+// #[derive(Clone, Debug)]
+// struct CameraTo3dStage {
+//     dim: usize,
+//     rot: na::Matrix<f32, na::Dyn, na::Dyn, na::VecStorage<f32, na::Dyn, na::Dyn>>,
+// }
+// impl CameraTo3dStage {
+//     fn new(dim: usize) -> Self {
+//         let golden_ratio = (1.0 + 5.0_f32.sqrt()) / 2.0;
+//         let mut rot = na::DMatrix::<f32>::identity(dim, dim);
+//         let last = dim - 1;
+//         for i in 0..last {
+//             let angle = std::f32::consts::FRAC_PI_4 / golden_ratio.powi(i as i32);
+//             let (s, c) = angle.sin_cos();
+//             // Right-multiply rot by G(i, last, angle):
+//             //   new col_i    =  c * col_i + s * col_last
+//             //   new col_last = -s * col_i + c * col_last
+//             let col_i: Vec<f32> = (0..dim).map(|r| rot[(r, i)]).collect();
+//             let col_last: Vec<f32> = (0..dim).map(|r| rot[(r, last)]).collect();
+//             for r in 0..dim {
+//                 rot[(r, i)] = c * col_i[r] + s * col_last[r];
+//                 rot[(r, last)] = -s * col_i[r] + c * col_last[r];
+//             }
+//         }
+//         Self { dim, rot }
+//     }
+
+//     /// returns (projected position, depth)
+//     fn project(&self, pos: &[f32]) -> (Box<[f32]>, f32) {
+//         assert_eq!(pos.len(), self.dim);
+//         let pos = &self.rot * na::DVector::from_column_slice(pos);
+//         let pos = pos.as_slice();
+//         // hack to test 4d perspective projection
+//         if let [x, y, z, w] = pos {
+//             let f = 1.0 / (1.0 + (1.0 - w));
+//             // let f = 1.0 / (1.0 + w);
+//             (vec![x * f, y * f, z * f].into(), *w)
+//         } else {
+//             let (last, pos) = pos.split_last().unwrap();
+//             (pos.into(), *last)
+//         }
+//     }
+// }
+
+// /// maps a nd point to a 3d point
+// #[derive(Clone, Debug)]
+// struct CameraTo3d {
+//     stages: Vec<CameraTo3dStage>,
+// }
+// impl CameraTo3d {
+//     fn new(dim: usize) -> Self {
+//         let stages = (4..=dim).rev().map(CameraTo3dStage::new).collect();
+//         CameraTo3d { stages }
+//     }
+
+//     fn project(&self, pos: &[f32]) -> Box<[f32]> {
+//         if self.stages.is_empty() {
+//             assert_eq!(pos.len(), 3);
+//             return pos.into();
+//         }
+//         assert_eq!(self.stages.first().unwrap().dim, pos.len());
+//         assert_eq!(self.stages.last().unwrap().dim, 4);
+//         let mut pos = pos.to_vec().into_boxed_slice();
+//         for stage in &self.stages {
+//             let (new_pos, depth) = stage.project(&pos);
+//             pos = new_pos;
+//         }
+//         pos
+//     }
+// }
+
+/// maps a nd point to a 4d point via a orthographic projection.
+#[derive(Clone, Debug)]
+struct CameraNdTo4d {
+    rot: na::Matrix<f32, na::U4, na::Dyn, na::VecStorage<f32, na::U4, na::Dyn>>,
+    /// how far each axis past the 4th is rotated into the first 4
+    angles: Vec<f32>,
+}
+impl CameraNdTo4d {
+    fn new(dim: usize) -> Self {
+        let golden_ratio = (1.0 + 5.0_f32.sqrt()) / 2.0;
+        let mut ret = Self {
+            rot: na::Matrix::<f32, na::U4, na::Dyn, na::VecStorage<f32, na::U4, na::Dyn>>::identity(
+                dim,
+            ),
+            angles: (4..dim)
+                .map(|axis| std::f32::consts::FRAC_PI_4 / golden_ratio.powi(axis as i32 - 4))
+                .collect(),
+        };
+        ret.rebuild();
+        ret
+    }
+
+    /// resets `rot` from `angles`
+    fn rebuild(&mut self) {
+        let golden_ratio = (1.0 + 5.0_f32.sqrt()) / 2.0;
+        let dim = self.rot.ncols();
+        let mut rot =
+            na::Matrix::<f32, na::U4, na::Dyn, na::VecStorage<f32, na::U4, na::Dyn>>::identity(dim);
+        for (j, angle) in (4..dim).zip(&self.angles) {
+            for i in 0..4 {
+                let angle = angle / golden_ratio.powi(i as i32);
+                let (s, c) = angle.sin_cos();
+                // Right-multiply rot by G(i, j, angle):
+                //   new col_i =  c * col_i + s * col_j
+                //   new col_j = -s * col_i + c * col_j
+                let col_i: Vec<f32> = (0..4).map(|r| rot[(r, i)]).collect();
+                let col_j: Vec<f32> = (0..4).map(|r| rot[(r, j)]).collect();
+                for r in 0..4 {
+                    rot[(r, i)] = c * col_i[r] + s * col_j[r];
+                    rot[(r, j)] = -s * col_i[r] + c * col_j[r];
+                }
+            }
+        }
+        self.rot = rot;
+    }
+
+    /// an orthonormal basis of the directions that don't show up in 4d
+    fn hidden(&self) -> Vec<na::DVector<f32>> {
+        let dim = self.rot.ncols();
+        let mut basis = (0..4)
+            .map(|r| self.rot.row(r).transpose().into_owned())
+            .map(|row| na::DVector::from_column_slice(row.as_slice()))
+            .collect::<Vec<_>>();
+        // the higher axes first, so the default view keeps them in order
+        for axis in (4..dim).chain(0..4) {
+            let mut v = na::DVector::<f32>::zeros(dim);
+            v[axis] = 1.0;
+            for b in &basis {
+                v -= b * b.dot(&v);
+            }
+            if v.norm() > 0.1 {
+                basis.push(v.normalize());
+            }
+        }
+        basis.split_off(4)
+    }
+
+    /// `hidden`, starting from the `first`th, so dragging can reach all of them
+    fn hidden_from(&self, first: usize) -> Vec<na::DVector<f32>> {
+        let mut hidden = self.hidden();
+        if !hidden.is_empty() {
+            let len = hidden.len();
+            hidden.rotate_left(first % len);
+        }
+        hidden
+    }
+
+    /// rotates the camera in the plane of `a` and `b` in nd,
+    /// which should be orthonormal
+    fn rotate(&mut self, a: &na::DVector<f32>, b: &na::DVector<f32>, angle: f32) {
+        // Givens rotation in plane (a, b):
+        //   R = I + (c-1)*(a*aT + b*bT) + s*(b*aT - a*bT)
+        let dim = self.rot.ncols();
+        let (s, c) = angle.sin_cos();
+        let r = na::DMatrix::<f32>::identity(dim, dim)
+            + (c - 1.0) * (a * a.transpose() + b * b.transpose())
+            + s * (b * a.transpose() - a * b.transpose());
+        let rot = na::DMatrix::from_column_slice(4, dim, self.rot.as_slice()) * r;
+        self.rot.copy_from_slice(rot.as_slice());
+    }
+
+    /// \[x, y, z, w]
+    fn project(&self, pos: &[f32]) -> [f32; 4] {
+        let pos = &self.rot * na::DVector::from_column_slice(pos);
+        *pos.as_slice().as_array::<4>().unwrap()
+    }
+}
+
+/// maps a 4d point to a 3d point via a perspective projection.
+#[derive(Clone, Debug)]
+struct Camera4dTo3d {
+    rot: na::Matrix4<f32>,
+    /// in degrees, 0 is orthographic
+    fov: f32,
+}
+impl Camera4dTo3d {
+    /// points closer to the camera than this get squashed onto it,
+    /// and count as behind it
+    const NEAR: f32 = 0.01;
+
+    fn new() -> Self {
+        Self {
+            rot: na::Matrix4::identity(),
+            fov: 90.0,
+        }
+    }
+
+    /// (\[x, y, z], depth)
+    fn project(&self, pos: &[f32; 4]) -> ([f32; 3], f32) {
+        for x in pos {
+            assert!(x.is_finite());
+        }
+        let pos = self.rot * na::DVector::from_column_slice(pos);
+        let [x, y, z, w] = *pos.as_slice().as_array().unwrap();
+        // the plane w = 1 keeps its size
+        let divisor = 1.0 + (1.0 - w) * (self.fov.to_radians() / 2.0).tan();
+        let f = 1.0 / divisor.max(Self::NEAR);
+        assert!(x.is_finite());
+        assert!(y.is_finite());
+        assert!(z.is_finite());
+        assert!(w.is_finite());
+        // ([x * f, y * f, z * f], w)
+        // negative behind the camera
+        let depth = (2.0 - w).abs();
+        let depth = if divisor > Self::NEAR { depth } else { -depth };
+        ([x * f, y * f, z * f], depth)
+    }
+
+    /// whether the camera is outside the cell with its middle at `pos`,
+    /// on a puzzle centered on the origin. those cells get drawn inside out.
+    fn is_outside(&self, pos: &[f32; 4]) -> bool {
+        let pos = self.rot * na::Vector4::from(*pos);
+        let t = (self.fov.to_radians() / 2.0).tan();
+        // the camera is at w = 1 + 1 / t, or infinitely far if orthographic
+        pos.w * (1.0 + t) > t * pos.norm_squared()
+    }
+}
+
+/// maps a 3d point to a 2d point via a perspective projection.
+#[derive(Clone, Debug)]
+struct Camera3dTo2d {
+    rot: na::Matrix3<f32>,
+    /// in degrees, 0 is orthographic
+    fov: f32,
+}
+impl Camera3dTo2d {
+    /// points closer to the camera than this get squashed onto it
+    const NEAR: f32 = 0.1;
+
+    fn new() -> Self {
+        Self {
+            rot: na::Matrix3::identity(),
+            fov: 0.0,
+        }
+    }
+
+    /// where the camera is after rotating, or None if it's orthographic.
+    /// it looks towards -z.
+    fn eye(&self) -> Option<na::Vector3<f32>> {
+        let t = (self.fov.to_radians() / 2.0).tan();
+        (t > 0.0).then(|| na::Vector3::new(0.0, 0.0, 1.0 + 1.0 / t))
+    }
+
+    fn rotate(&self, pos: &[f32; 3]) -> na::Vector3<f32> {
+        self.rot * na::Vector3::from(*pos)
+    }
+
+    // (\[x, y], depth)
+    fn project(&self, pos: &[f32; 3]) -> ([f32; 2], f32) {
+        for x in pos {
+            assert!(x.is_finite());
+        }
+        self.project_rotated(&self.rotate(pos))
+    }
+
+    /// `project` for a point that's already been rotated
+    fn project_rotated(&self, pos: &na::Vector3<f32>) -> ([f32; 2], f32) {
+        let [x, y, z] = *pos.as_slice().as_array().unwrap();
+        // the plane z = 0 keeps its size
+        let t = (self.fov.to_radians() / 2.0).tan();
+        let f = (1.0 + t) / (1.0 + (1.0 - z) * t).max(Self::NEAR);
+        let (x, y) = (x * f, y * f);
+        assert!(x.is_finite());
+        assert!(y.is_finite());
+        assert!(z.is_finite());
+        ([x, y], z)
+    }
+}
+
+/// a flat convex polygon in 3d, for splitting stickers where they cross,
+/// so that sorting by depth draws them in the right order
+#[derive(Clone, Debug)]
+struct Polygon {
+    verts: Vec<na::Vector3<f32>>,
+    /// whether the edge from each vert to the next is an edge of the original polygon,
+    /// rather than where it was split
+    edges: Vec<bool>,
+}
+impl Polygon {
+    /// closer than this counts as touching
+    const EPSILON: f32 = 1e-5;
+
+    fn new(verts: Vec<na::Vector3<f32>>) -> Self {
+        let edges = vec![true; verts.len()];
+        Polygon { verts, edges }
+    }
+
+    /// the unit normal and distance from the origin of the plane it's in,
+    /// or None if it's too small to have one
+    fn plane(&self) -> Option<(na::Vector3<f32>, f32)> {
+        let normal = self
+            .verts
+            .iter()
+            .circular_tuple_windows()
+            .map(|(a, b)| a.cross(b))
+            .sum::<na::Vector3<f32>>()
+            .try_normalize(Self::EPSILON)?;
+        Some((normal, normal.dot(&self.verts[0])))
+    }
+
+    fn bounds(&self) -> (na::Vector3<f32>, na::Vector3<f32>) {
+        let min = self.verts.iter().fold(self.verts[0], |acc, v| acc.inf(v));
+        let max = self.verts.iter().fold(self.verts[0], |acc, v| acc.sup(v));
+        (min, max)
+    }
+
+    /// the signed distance of each vert from `plane`,
+    /// or None if they're all on one side
+    fn distances(&self, (normal, offset): (na::Vector3<f32>, f32)) -> Option<Vec<f32>> {
+        let distances = self
+            .verts
+            .iter()
+            .map(|vert| normal.dot(vert) - offset)
+            .collect_vec();
+        let straddles = distances.iter().any(|&d| d > Self::EPSILON)
+            && distances.iter().any(|&d| d < -Self::EPSILON);
+        straddles.then_some(distances)
+    }
+
+    /// the parts in front of and behind `plane`, or None if it doesn't go through the middle
+    fn split(&self, plane: (na::Vector3<f32>, f32)) -> Option<[Polygon; 2]> {
+        let distances = self.distances(plane)?;
+        let n = self.verts.len();
+        let clip = |sign: f32| {
+            let mut part = Polygon::new(Vec::new());
+            part.edges.clear();
+            for i in 0..n {
+                let (a, b) = (self.verts[i], self.verts[(i + 1) % n]);
+                let (da, db) = (sign * distances[i], sign * distances[(i + 1) % n]);
+                let crossing = || a + (b - a) * (da / (da - db));
+                if da >= 0.0 {
+                    part.verts.push(a);
+                    if db < 0.0 && da > 0.0 {
+                        part.edges.push(self.edges[i]);
+                        part.verts.push(crossing());
+                    }
+                    // from where it leaves the plane's side to where it comes back is the cut
+                    part.edges.push(self.edges[i] && db >= 0.0);
+                } else if db > 0.0 {
+                    part.verts.push(crossing());
+                    part.edges.push(self.edges[i]);
+                }
+            }
+            part
+        };
+        Some([clip(1.0), clip(-1.0)])
+    }
+
+    /// whether it goes through `other`, rather than just touching it or being beside it
+    fn crosses(&self, other: &Polygon) -> bool {
+        let (Some(a), Some(b)) = (self.plane(), other.plane()) else {
+            return false;
+        };
+        // the line where the planes meet
+        let dir = a.0.cross(&b.0);
+        if dir.norm() < Self::EPSILON {
+            return false;
+        }
+        // where each polygon meets the other's plane, along that line
+        let interval = |polygon: &Polygon, plane| {
+            let distances = polygon.distances(plane)?;
+            let n = polygon.verts.len();
+            (0..n)
+                .filter_map(|i| {
+                    let (a, b) = (polygon.verts[i], polygon.verts[(i + 1) % n]);
+                    let (da, db) = (distances[i], distances[(i + 1) % n]);
+                    (da.signum() != db.signum()).then(|| dir.dot(&(a + (b - a) * (da / (da - db)))))
+                })
+                .minmax()
+                .into_option()
+        };
+        let (Some(self_interval), Some(other_interval)) = (interval(self, b), interval(other, a))
+        else {
+            return false;
+        };
+        self_interval.0.max(other_interval.0) + Self::EPSILON
+            < self_interval.1.min(other_interval.1)
+    }
+
+    /// splits each polygon along the planes of the ones it crosses.
+    /// only the ones whose bounding boxes overlap are checked, sweeping along x.
+    #[inline(never)]
+    fn split_crossing<T: Clone>(polygons: Vec<(Polygon, T)>) -> Vec<(Polygon, T)> {
+        let bounds = polygons
+            .iter()
+            .map(|(polygon, _)| polygon.bounds())
+            .collect_vec();
+        let mut parts = polygons
+            .iter()
+            .map(|(polygon, _)| vec![polygon.clone()])
+            .collect_vec();
+        let mut active: Vec<usize> = Vec::new();
+        for i in (0..polygons.len()).sorted_by(|&i, &j| bounds[i].0.x.total_cmp(&bounds[j].0.x)) {
+            active.retain(|&j| bounds[j].1.x >= bounds[i].0.x);
+            for &j in &active {
+                let overlap = (0..3)
+                    .all(|k| bounds[i].0[k] <= bounds[j].1[k] && bounds[j].0[k] <= bounds[i].1[k]);
+                if !overlap || !polygons[i].0.crosses(&polygons[j].0) {
+                    continue;
+                }
+                for (a, b) in [(i, j), (j, i)] {
+                    let plane = polygons[b].0.plane().unwrap();
+                    parts[a] = parts[a]
+                        .iter()
+                        .flat_map(|part| match part.split(plane) {
+                            Some(split) => split.to_vec(),
+                            None => vec![part.clone()],
+                        })
+                        .collect();
+                }
+            }
+            active.push(i);
+        }
+        polygons
+            .into_iter()
+            .zip(parts)
+            .flat_map(|((_, data), parts)| parts.into_iter().map(move |part| (part, data.clone())))
+            .collect()
+    }
+}
+
+/// which cells to hide in 4d and up
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CellCulling {
+    None,
+    /// the one closest to the 4d camera
+    Nearest,
+    /// the ones the 4d camera sees from outside
+    FacingAway,
+    /// the ones not in `App::shown_sides`
+    Selected,
+}
+
+/// the rotations of every camera stage, for presets and smooth transitions
+#[derive(Clone, Debug)]
+struct ViewState {
+    rot_nd: na::DMatrix<f32>,
+    rot_4d: na::Matrix4<f32>,
+    rot_3d: na::Matrix3<f32>,
+}
+impl ViewState {
+    fn get(
+        cam_nd_to_4d: &CameraNdTo4d,
+        cam_4d_to_3d: &Camera4dTo3d,
+        cam_3d_to_2d: &Camera3dTo2d,
+    ) -> Self {
+        let dim = cam_nd_to_4d.rot.ncols();
+        ViewState {
+            rot_nd: na::DMatrix::from_column_slice(4, dim, cam_nd_to_4d.rot.as_slice()),
+            rot_4d: cam_4d_to_3d.rot,
+            rot_3d: cam_3d_to_2d.rot,
+        }
+    }
+
+    fn set(
+        &self,
+        cam_nd_to_4d: &mut CameraNdTo4d,
+        cam_4d_to_3d: &mut Camera4dTo3d,
+        cam_3d_to_2d: &mut Camera3dTo2d,
+    ) {
+        cam_nd_to_4d.rot.copy_from_slice(self.rot_nd.as_slice());
+        cam_4d_to_3d.rot = self.rot_4d;
+        cam_3d_to_2d.rot = self.rot_3d;
+    }
+
+    /// the view when the puzzle is made
+    fn default(dim: usize) -> Self {
+        ViewState::get(
+            &CameraNdTo4d::new(dim),
+            &Camera4dTo3d::new(),
+            &Camera3dTo2d::new(),
+        )
+    }
+
+    /// looking down the corner between R, U and F
+    fn isometric(dim: usize) -> Self {
+        ViewState {
+            rot_3d: *na::Rotation3::look_at_rh(
+                &-na::Vector3::new(1.0, 1.0, 1.0),
+                &na::Vector3::y(),
+            )
+            .matrix(),
+            ..Self::default(dim)
+        }
+    }
+
+    /// looking straight at `side`, or with its cell in the middle for 4d and up
+    fn face_on(dim: usize, side: Side) -> Self {
+        let mut ret = Self::default(dim);
+        let axis = side.axis().into_usize();
+        let sign = if side.is_positive() { 1.0 } else { -1.0 };
+        if axis < 3 {
+            let mut dir = na::Vector3::zeros();
+            dir[axis] = sign;
+            // U and D are looked at with F towards the bottom and top of the screen
+            let up = if axis == 1 {
+                na::Vector3::new(0.0, 0.0, -sign)
+            } else {
+                na::Vector3::y()
+            };
+            ret.rot_3d = *na::Rotation3::look_at_rh(&-dir, &up).matrix();
+        } else {
+            if dim >= 5 {
+                // the first 3 axes and this one, with the rest hidden
+                ret.rot_nd.fill(0.0);
+                for (row, col) in [0, 1, 2, axis].into_iter().enumerate() {
+                    ret.rot_nd[(row, col)] = 1.0;
+                }
+            }
+            // the cell in the middle is the one at -w
+            if side.is_positive() {
+                ret.rot_4d = na::Matrix4::from_diagonal(&na::Vector4::new(1.0, 1.0, -1.0, -1.0));
+            }
+        }
+        ret
+    }
+
+    /// the view with the puzzle rotated by `symmetry` first
+    fn rotated(&self, symmetry: &Symmetry) -> Self {
+        let m = symmetry.matrix();
+        let mut ret = self.clone();
+        match m.ncols() {
+            0..3 => {}
+            3 => ret.rot_3d *= na::Matrix3::from_column_slice(m.as_slice()),
+            4 => ret.rot_4d *= na::Matrix4::from_column_slice(m.as_slice()),
+            _ => ret.rot_nd = &self.rot_nd * m,
+        }
+        ret
+    }
+
+    /// the nearest view where every visible axis is lined up with the screen,
+    /// for the stages in `stages` out of 3d, 4d and nd
+    fn snapped(&self, stages: [bool; 3]) -> Self {
+        // the rotation matrix of a signed permutation that's closest to `rot`
+        fn snap<const N: usize>(rot: &na::SMatrix<f32, N, N>) -> na::SMatrix<f32, N, N> {
+            let matrix = |symmetry: &Symmetry| {
+                let mut m = na::SMatrix::<f32, N, N>::zeros();
+                for (i, side) in symmetry.0.iter().enumerate() {
+                    m[(side.axis().into_usize(), i)] = if side.is_positive() { 1.0 } else { -1.0 };
+                }
+                m
+            };
+            Symmetry::rotations(&[Cut(1); N])
+                .iter()
+                .map(matrix)
+                .max_by(|a, b| a.dot(rot).total_cmp(&b.dot(rot)))
+                .unwrap()
+        }
+        let mut ret = self.clone();
+        if stages[0] {
+            ret.rot_3d = snap(&self.rot_3d);
+        }
+        if stages[1] {
+            ret.rot_4d = snap(&self.rot_4d);
+        }
+        let dim = self.rot_nd.ncols();
+        // below 4d there's nothing to snap
+        if stages[2] && dim >= 4 {
+            // each row picks a different axis, with the sign it's closest to
+            let best = (0..dim)
+                .permutations(4)
+                .max_by(|a, b| {
+                    let score = |cols: &Vec<usize>| -> f32 {
+                        cols.iter()
+                            .enumerate()
+                            .map(|(row, &col)| self.rot_nd[(row, col)].abs())
+                            .sum()
+                    };
+                    score(a).total_cmp(&score(b))
+                })
+                .unwrap();
+            ret.rot_nd.fill(0.0);
+            for (row, col) in best.into_iter().enumerate() {
+                ret.rot_nd[(row, col)] = self.rot_nd[(row, col)].signum();
+            }
+        }
+        ret
+    }
+
+    /// `t` of the way from `self` to `other`.
+    /// not quite constant speed, but it ends up at `other`.
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let q_from = na::UnitQuaternion::from_matrix(&self.rot_3d);
+        let q_to = na::UnitQuaternion::from_matrix(&other.rot_3d);
+        // rows of the lerped matrix, made orthonormal again
+        fn lerp_rows(from: &na::DMatrix<f32>, to: &na::DMatrix<f32>, t: f32) -> na::DMatrix<f32> {
+            let mut ret = from * (1.0 - t) + to * t;
+            for row in 0..ret.nrows() {
+                // fall back to other rows if the lerp passes through 0
+                let candidates = [
+                    ret.row(row).into_owned(),
+                    to.row(row).into_owned(),
+                    from.row(row).into_owned(),
+                ];
+                let orthogonal = candidates
+                    .into_iter()
+                    .map(|mut v| {
+                        for prev in 0..row {
+                            let prev = ret.row(prev).into_owned();
+                            v -= &prev * prev.dot(&v);
+                        }
+                        v
+                    })
+                    .find(|v| v.norm() > 1e-3)
+                    .unwrap_or_else(|| to.row(row).into_owned());
+                ret.set_row(row, &orthogonal.normalize());
+            }
+            ret
+        }
+        let square = |m: &na::Matrix4<f32>| na::DMatrix::from_column_slice(4, 4, m.as_slice());
+        ViewState {
+            rot_nd: lerp_rows(&self.rot_nd, &other.rot_nd, t),
+            rot_4d: na::Matrix4::from_column_slice(
+                lerp_rows(&square(&self.rot_4d), &square(&other.rot_4d), t).as_slice(),
+            ),
+            rot_3d: *q_from.slerp(&q_to, t).to_rotation_matrix().matrix(),
+        }
+    }
+}
+
+/// a smooth change of view
+#[derive(Clone, Debug)]
+struct ViewTransition {
+    from: ViewState,
+    to: ViewState,
+    /// in seconds, from `egui::InputState::time`
+    start: f64,
+}
+impl ViewTransition {
+    const DURATION: f64 = 0.3;
+}
+
+#[derive(Clone, Debug)]
+struct StickerFormatBuilder {
+    outline_color: Option<Color32>,
+    outline_width: Option<f32>,
+    sticker_scale: Option<f32>,
+    sticker_opacity: Option<f32>,
+}
+impl StickerFormatBuilder {
+    pub const NONE: Self = StickerFormatBuilder {
+        outline_color: None,
+        outline_width: None,
+        sticker_scale: None,
+        sticker_opacity: None,
+    };
+
+    fn update(&mut self, other: &StickerFormatBuilder) {
+        self.outline_color = self.outline_color.or(other.outline_color);
+        self.outline_width = self.outline_width.or(other.outline_width);
+        self.sticker_scale = self.sticker_scale.or(other.sticker_scale);
+        self.sticker_opacity = self.sticker_opacity.or(other.sticker_opacity);
+    }
+
+    /// `outline_color outline_width sticker_scale sticker_opacity`, with `-` for None
+    fn to_setting(&self) -> String {
+        let float = |x: Option<f32>| x.map_or("-".into(), |x| x.to_string());
+        format!(
+            "{} {} {} {}",
+            self.outline_color
+                .map_or("-".into(), |color| color.to_hex()),
+            float(self.outline_width),
+            float(self.sticker_scale),
+            float(self.sticker_opacity)
+        )
+    }
+
+    fn from_setting(setting: &str) -> Option<Self> {
+        let fields = setting.split_whitespace().collect::<Vec<_>>();
+        let [outline_color, outline_width, sticker_scale, sticker_opacity] = fields[..] else {
+            return None;
+        };
+        // Some(None) for `-`
+        let float = |field: &str| match field {
+            "-" => Some(None),
+            _ => field
+                .parse::<f32>()
+                .ok()
+                .filter(|x| (0.0..=1.0).contains(x))
+                .map(Some),
+        };
+        Some(StickerFormatBuilder {
+            outline_color: match outline_color {
+                "-" => None,
+                _ => Some(Color32::from_hex(outline_color).ok()?),
+            },
+            outline_width: float(outline_width)?,
+            sticker_scale: float(sticker_scale)?,
+            sticker_opacity: float(sticker_opacity)?,
+        })
+    }
+
+    fn build_or(&self, default: &StickerFormat) -> StickerFormat {
+        StickerFormat {
+            outline_color: self.outline_color.unwrap_or(default.outline_color),
+            outline_width: self.outline_width.unwrap_or(default.outline_width),
+            sticker_scale: self.sticker_scale.unwrap_or(default.sticker_scale),
+            sticker_opacity: self.sticker_opacity.unwrap_or(default.sticker_opacity),
+        }
+    }
+}
+
+// TODO: better name
+#[derive(Clone, Debug)]
+struct StickerFormat {
+    outline_color: Color32,
+    /// lives in [0.0, 1.0],
+    /// where 1.0 is the size of a sticker
+    outline_width: f32,
+    /// lives in [0.0, 1.0]
+    sticker_scale: f32,
+    /// lives in [0.0, 1.0]
+    sticker_opacity: f32,
+}
+impl StickerFormat {
+    fn to_setting(&self) -> String {
+        StickerFormatBuilder {
+            outline_color: Some(self.outline_color),
+            outline_width: Some(self.outline_width),
+            sticker_scale: Some(self.sticker_scale),
+            sticker_opacity: Some(self.sticker_opacity),
+        }
+        .to_setting()
+    }
+
+    /// like `StickerFormatBuilder::from_setting`, but without any `-`
+    fn from_setting(setting: &str) -> Option<Self> {
+        let builder = StickerFormatBuilder::from_setting(setting)?;
+        Some(StickerFormat {
+            outline_color: builder.outline_color?,
+            outline_width: builder.outline_width?,
+            sticker_scale: builder.sticker_scale?,
+            sticker_opacity: builder.sticker_opacity?,
+        })
+    }
+}
+// impl Default for StickerFormat {
+//     fn default() -> Self {
+//         StickerFormat {
+//             outline_color: Color32::from_rgb(100, 100, 100),
+//             outline_width: 0.02,
+//             sticker_scale: 1.0,
+//             sticker_opacity: 1.0,
+//         }
+//     }
+// }
+
+#[derive(Clone, Debug)]
+struct FilterTerm {
+    must_have: HashSet<Side>,
+    cant_have: HashSet<Side>,
+}
+impl FilterTerm {
+    fn new() -> Self {
+        FilterTerm {
+            must_have: HashSet::new(),
+            cant_have: HashSet::new(),
+        }
+    }
+
+    fn contains(&self, shape: &[Cut], piece: &Piece) -> bool {
+        for side in piece.sides(shape) {
+            if !self.must_have.contains(&side) {
+                return false;
+            }
+            if self.cant_have.contains(&side) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// the pieces with all of `must_have` and none of `cant_have`, and any other sides,
+    /// for picking pieces to solve rather than pieces to show
+    fn selects(&self, shape: &[Cut], piece: &Piece) -> bool {
+        let sides = piece.sides(shape).collect::<HashSet<_>>();
+        self.must_have.iter().all(|side| sides.contains(side))
+            && !self.cant_have.iter().any(|side| sides.contains(side))
+    }
+}
+
+/// the filter contains a piece if it matches any of the terms
+#[derive(Clone, Debug)]
+struct Filter {
+    terms: Vec<FilterTerm>,
+    format: StickerFormatBuilder,
+}
+impl Filter {
+    fn contains(&self, shape: &[Cut], piece: &Piece) -> bool {
+        self.terms.iter().any(|term| term.contains(shape, piece))
+    }
+
+    fn selects(&self, shape: &[Cut], piece: &Piece) -> bool {
+        self.terms.iter().any(|term| term.selects(shape, piece))
+    }
+}
+
+/// a entire filter stage is rendered at once,
+/// with the last Some property of the filter being applied to that piece
+#[derive(Clone, Debug)]
+struct FilterStage(Vec<Filter>);
+impl FilterStage {
+    // fn try_get(&self, shape: &[Cut], piece: &[Coord]) -> Option<StickerFormatBuilder> {
+    //     for filter in &self.0 {
+    //         if let Some(format) = filter.try_get(shape, piece) {
+    //             return Some(format);
+    //         }
+    //     }
+    //     None
+    // }
+}
+
+#[derive(Clone, Debug)]
+struct FilterSequence(Vec<FilterStage>);
+// impl FilterSequence {
+//     fn new() -> Self {
+//         FilterSequence(Vec::new())
+//     }
+// }
+
+/// where settings and libraries are stored
+fn config_dir() -> std::path::PathBuf {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| std::path::Path::new(&home).join(".config"))
+        })
+        .unwrap_or_else(|| ".".into())
+        .join("rectangle")
+}
+
+/// a named sequence of turns
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Macro {
+    name: String,
+    /// the side that was gripped when the macro was recorded,
+    /// so it can be replayed on a different side
+    grip: Side,
+    turns: Vec<Turn>,
+}
+impl Macro {
+    fn new(name: String, turns: Vec<Turn>) -> Self {
+        let grip = turns
+            .iter()
+            .find_map(|turn| match turn {
+                Turn::Side(turn) => Some(turn.side),
+                Turn::Puzzle(_) => None,
+            })
+            .unwrap_or(Side::new(0));
+        Macro { name, grip, turns }
+    }
+
+    /// the turns to do this macro with `grip` gripped instead,
+    /// or None if the puzzle doesn't have such a symmetry
+    fn turns_at(&self, shape: &[Cut], grip: Side) -> Option<Vec<Turn>> {
+        if self.turns.iter().any(|turn| turn.dim() > shape.len()) {
+            return None;
+        }
+        let symmetry = Symmetry::taking(shape, self.grip, grip)?;
+        Some(self.turns.iter().map(|turn| symmetry.turn(turn)).collect())
+    }
+
+    /// the left or right handed version, swapping the sides of `axis`
+    fn mirrored(&self, axis: Axis) -> Self {
+        let symmetry = Symmetry::mirror(self.dim().max(axis.into_usize() + 1), axis);
+        Macro {
+            name: self.name.clone(),
+            grip: symmetry.side(self.grip),
+            turns: self.turns.iter().map(|turn| symmetry.turn(turn)).collect(),
+        }
+    }
+
+    /// the number of axes needed to play this macro
+    fn dim(&self) -> usize {
+        self.turns
+            .iter()
+            .map(Turn::dim)
+            .chain(once(self.grip.axis().into_usize() + 1))
+            .max()
+            .unwrap()
+    }
+
+    /// `name<TAB>grip<TAB>turns`
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}",
+            self.name,
+            self.grip.name(),
+            self.turns.iter().join(" ")
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let name = fields.next()?.to_string();
+        let mut grip = fields.next()?.chars();
+        let grip = Side::try_from_name(grip.next()?).filter(|_| grip.next().is_none())?;
+        let turns = Turn::parse_seq(fields.next()?).ok()?;
+        if fields.next().is_some() {
+            return None;
+        }
+        Some(Macro { name, grip, turns })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct MacroLibrary(Vec<Macro>);
+impl MacroLibrary {
+    fn path() -> std::path::PathBuf {
+        config_dir().join("macros.txt")
+    }
+
+    /// skips lines that don't parse
+    fn load() -> Self {
+        let Ok(file) = std::fs::read_to_string(Self::path()) else {
+            return MacroLibrary(Vec::new());
+        };
+        MacroLibrary(
+            file.lines()
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .filter_map(|line| {
+                    let ret = Macro::from_line(line);
+                    if ret.is_none() {
+                        println!("couldn't parse macro {line:?}");
+                    }
+                    ret
+                })
+                .collect(),
+        )
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = String::from("# name\tgrip\tturns\n");
+        for m in &self.0 {
+            file.push_str(&m.to_line());
+            file.push('\n');
+        }
+        std::fs::write(path, file)
+    }
+}
+
+/// colors for the sides, falling back to `Side::color`,
+/// or to `Side::generated_color` past the end of a palette
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ColorScheme {
+    name: String,
+    colors: HashMap<Side, Color32>,
+}
+impl ColorScheme {
+    /// the default colors, and ones that are easier to tell apart with color blindness,
+    /// from Okabe and Ito, and from Paul Tol
+    fn builtin() -> Vec<Self> {
+        let scheme = |name: &str, colors: &[(char, u32)]| ColorScheme {
+            name: name.into(),
+            colors: colors
+                .iter()
+                .map(|&(side, rgb)| {
+                    let [_, r, g, b] = rgb.to_be_bytes();
+                    (
+                        Side::try_from_name(side).unwrap(),
+                        Color32::from_rgb(r, g, b),
+                    )
+                })
+                .collect(),
+        };
+        vec![
+            scheme("default", &[]),
+            scheme(
+                "okabe-ito",
+                &[
+                    ('R', 0xd55e00),
+                    ('L', 0xe69f00),
+                    ('U', 0xffffff),
+                    ('D', 0xf0e442),
+                    ('F', 0x009e73),
+                    ('B', 0x0072b2),
+                    ('O', 0xcc79a7),
+                    ('I', 0x56b4e9),
+                ],
+            ),
+            scheme(
+                "tol bright",
+                &[
+                    ('R', 0xee6677),
+                    ('L', 0xccbb44),
+                    ('U', 0xffffff),
+                    ('D', 0xbbbbbb),
+                    ('F', 0x228833),
+                    ('B', 0x4477aa),
+                    ('O', 0xaa3377),
+                    ('I', 0x66ccee),
+                ],
+            ),
+        ]
+    }
+
+    fn color(&self, side: Side) -> Color32 {
+        if let Some(color) = self.colors.get(&side) {
+            return *color;
+        }
+        // the usual colors would clash with a palette
+        if side.axis().into_usize() >= self.palette_axes() && self.palette_axes() > 0 {
+            side.generated_color()
+        } else {
+            side.color()
+        }
+    }
+
+    /// how many axes from the first have colors on both sides
+    fn palette_axes(&self) -> usize {
+        (0..)
+            .map(Side::new)
+            .take_while(|&side| self.colors.contains_key(&side) && self.colors.contains_key(&!side))
+            .count()
+    }
+
+    /// `name<TAB>R=#rrggbbaa L=#rrggbbaa ...`
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}",
+            self.name,
+            self.colors
+                .iter()
+                .sorted_by_key(|(side, _)| (side.axis(), !side.is_positive()))
+                .map(|(side, color)| format!("{}={}", side.name(), color.to_hex()))
+                .join(" ")
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let (name, colors) = line.split_once('\t')?;
+        let colors = colors
+            .split_whitespace()
+            .map(|field| {
+                let (side, color) = field.split_once('=')?;
+                let mut side = side.chars();
+                let side = Side::try_from_name(side.next()?).filter(|_| side.next().is_none())?;
+                Some((side, Color32::from_hex(color).ok()?))
+            })
+            .collect::<Option<_>>()?;
+        Some(ColorScheme {
+            name: name.into(),
+            colors,
+        })
+    }
+}
+
+/// the color schemes the user saved
+#[derive(Clone, Debug)]
+struct ColorSchemes(Vec<ColorScheme>);
+impl ColorSchemes {
+    fn path() -> std::path::PathBuf {
+        config_dir().join("color_schemes.txt")
+    }
+
+    /// skips lines that don't parse
+    fn load() -> Self {
+        let Ok(file) = std::fs::read_to_string(Self::path()) else {
+            return ColorSchemes(Vec::new());
+        };
+        ColorSchemes(
+            file.lines()
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .filter_map(|line| {
+                    let ret = ColorScheme::from_line(line);
+                    if ret.is_none() {
+                        println!("couldn't parse color scheme {line:?}");
+                    }
+                    ret
+                })
+                .collect(),
+        )
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = String::from("# name\tside=#rrggbbaa ...\n");
+        for scheme in &self.0 {
+            file.push_str(&scheme.to_line());
+            file.push('\n');
+        }
+        std::fs::write(path, file)
+    }
+}
+
+/// time spent solving, from the first turn after a scramble until it's solved
+#[derive(Clone, Debug, PartialEq)]
+struct Timer {
+    /// seconds, not counting since `start`
+    elapsed: f64,
+    /// the egui time it was last started, if it's running
+    start: Option<f64>,
+    /// whether the puzzle got solved, so it shouldn't start again
+    done: bool,
+}
+impl Timer {
+    fn new() -> Self {
+        Timer {
+            elapsed: 0.0,
+            start: None,
+            done: false,
+        }
+    }
+
+    fn seconds(&self, time: f64) -> f64 {
+        self.elapsed + self.start.map_or(0.0, |start| time - start)
+    }
+
+    /// `m:ss.s`
+    fn format(&self, time: f64) -> String {
+        let seconds = self.seconds(time);
+        format!("{}:{:04.1}", (seconds / 60.0) as u64, seconds % 60.0)
+    }
+}
+
+/// everything needed to carry on solving, as `key = value` lines
+#[derive(Clone, Debug)]
+struct SavedState {
+    puzzle: Puzzle,
+    scramble: Vec<Turn>,
+    history: Vec<Turn>,
+    /// seconds on the timer, and whether it was running or done
+    timer: (f64, bool, bool),
+    orientation: Symmetry,
+    view: ViewState,
+}
+impl SavedState {
+    /// how many old saves to keep, in case the newest is broken
+    const BACKUPS: usize = 2;
+    /// how often to save, in seconds
+    const INTERVAL: f64 = 10.0;
+
+    /// newest first
+    fn paths() -> Vec<std::path::PathBuf> {
+        once(config_dir().join("autosave.txt"))
+            .chain((1..=Self::BACKUPS).map(|i| config_dir().join(format!("autosave.{i}.txt"))))
+            .collect()
+    }
+
+    fn to_text(&self) -> String {
+        let floats = |xs: &[f32]| xs.iter().join(" ");
+        let (seconds, running, done) = self.timer;
+        [
+            format!(
+                "shape = {}",
+                self.puzzle.shape.iter().map(|cut| cut.0).join("x")
+            ),
+            format!("stickers = {}", self.puzzle.stickers_to_string()),
+            format!("scramble = {}", Turn::format_seq(&self.scramble)),
+            format!("history = {}", Turn::format_seq(&self.history)),
+            format!("timer = {seconds} {running} {done}"),
+            format!(
+                "orientation = {}",
+                self.orientation
+                    .0
+                    .iter()
+                    .map(|side| side.name())
+                    .collect::<String>()
+            ),
+            format!("view_nd = {}", floats(self.view.rot_nd.as_slice())),
+            format!("view_4d = {}", floats(self.view.rot_4d.as_slice())),
+            format!("view_3d = {}", floats(self.view.rot_3d.as_slice())),
+        ]
+        .into_iter()
+        .map(|line| line + "\n")
+        .collect()
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let fields = text
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .collect::<HashMap<_, _>>();
+        let field = |key: &str| fields.get(key).copied().ok_or(format!("missing {key}"));
+        let floats = |key: &str, len: usize| {
+            let xs = field(key)?
+                .split_whitespace()
+                .map(|x| x.parse::<f32>().ok().filter(|x| x.is_finite()))
+                .collect::<Option<Vec<_>>>()
+                .ok_or(format!("bad {key}"))?;
+            if xs.len() == len {
+                Ok(xs)
+            } else {
+                Err(format!("wrong length of {key}"))
+            }
+        };
+
+        let shape = Cut::parse_shape(field("shape")?).ok_or("bad shape")?;
+        let dim = shape.len();
+        let puzzle =
+            Puzzle::from_stickers_string(&shape, field("stickers")?).ok_or("bad stickers")?;
+        let turns = |key: &str| {
+            let turns = Turn::parse_seq(field(key)?).map_err(|err| format!("{key}: {err}"))?;
+            if turns.iter().any(|turn| turn.dim() > dim) {
+                return Err(format!("{key} doesn't fit the shape"));
+            }
+            Ok(turns)
+        };
+        let timer = match field("timer")?.split_whitespace().collect::<Vec<_>>()[..] {
+            [seconds, running, done] => (
+                seconds
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|x| x.is_finite() && *x >= 0.0),
+                running.parse().ok(),
+                done.parse().ok(),
+            ),
+            _ => (None, None, None),
+        };
+        let (Some(seconds), Some(running), Some(done)) = timer else {
+            return Err("bad timer".into());
+        };
+        let orientation = field("orientation")?
+            .chars()
+            .map(|name| Side::try_from_name(name).filter(|side| side.axis().into_usize() < dim))
+            .collect::<Option<Vec<_>>>()
+            .filter(|sides| sides.len() == dim && sides.iter().map(|side| side.axis()).all_unique())
+            .ok_or("bad orientation")?;
+        Ok(SavedState {
+            scramble: turns("scramble")?,
+            history: turns("history")?,
+            puzzle,
+            timer: (seconds, running, done),
+            orientation: Symmetry(orientation),
+            view: ViewState {
+                rot_nd: na::DMatrix::from_column_slice(4, dim, &floats("view_nd", 4 * dim)?),
+                rot_4d: na::Matrix4::from_column_slice(&floats("view_4d", 16)?),
+                rot_3d: na::Matrix3::from_column_slice(&floats("view_3d", 9)?),
+            },
+        })
+    }
+
+    /// the newest save that isn't broken
+    fn load() -> Option<Self> {
+        Self::paths().into_iter().find_map(|path| {
+            let text = std::fs::read_to_string(&path).ok()?;
+            Self::parse(&text)
+                .inspect_err(|err| println!("couldn't load {}: {err}", path.display()))
+                .ok()
+        })
+    }
+
+    /// moves the older saves back, and writes this one in one go
+    fn save(text: &str) -> std::io::Result<()> {
+        let paths = Self::paths();
+        std::fs::create_dir_all(config_dir())?;
+        let temp = config_dir().join("autosave.tmp");
+        std::fs::write(&temp, text)?;
+        for (older, newer) in paths.iter().rev().tuple_windows() {
+            if newer.exists() {
+                std::fs::rename(newer, older)?;
+            }
+        }
+        std::fs::rename(temp, &paths[0])
+    }
+}
+
+/// what an algorithm would do to the puzzle
+#[derive(Clone, Debug)]
+struct AlgPreview {
+    turns: Vec<Turn>,
+    permutation: StickerPermutation,
+    /// the pieces that would get moved or twisted
+    moved_pieces: HashSet<Piece>,
+    cycle_structure: String,
+    order: Option<u64>,
+    solved_order: Option<u64>,
+    orbit_parities: Vec<(String, bool)>,
+    piece_cycles: Vec<String>,
+    sticker_cycles: Vec<String>,
+}
+impl AlgPreview {
+    #[inline(never)]
+    fn new(shape: &[Cut], turns: Vec<Turn>) -> Result<Self, String> {
+        if let Some(dim) = turns.iter().map(Turn::dim).max()
+            && dim > shape.len()
+        {
+            return Err(format!("needs at least {dim} dimensions"));
+        }
+        let permutation =
+            StickerPermutation::new(shape, &turns).map_err(|err| format!("{err:?}"))?;
+        let piece_cycles = permutation
+            .piece_cycles()
+            .into_iter()
+            .map(|(cycle, twisted)| {
+                format!(
+                    "({}){}",
+                    cycle.iter().map(|piece| piece.name(shape)).join(" "),
+                    if twisted { " twisted" } else { "" }
+                )
+            })
+            .sorted()
+            .collect();
+        let sticker_cycles = permutation
+            .cycles()
+            .into_iter()
+            .map(|cycle| {
+                format!(
+                    "({})",
+                    cycle.iter().map(|sticker| sticker.name(shape)).join(" ")
+                )
+            })
+            .sorted()
+            .collect();
+        Ok(AlgPreview {
+            turns,
+            moved_pieces: permutation.piece_sources().into_keys().collect(),
+            cycle_structure: permutation.cycle_structure(),
+            order: permutation.order(),
+            solved_order: permutation.solved_order(),
+            orbit_parities: permutation.orbit_parities(),
+            piece_cycles,
+            sticker_cycles,
+            permutation,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct App {
+    puzzle: Puzzle,
+    cam_nd_to_4d: CameraNdTo4d,
+    cam_4d_to_3d: Camera4dTo3d,
+    cam_3d_to_2d: Camera3dTo2d,
+    /// the size of a sticker in its cell, 1 to touch its neighbours
+    sticker_size: f32,
+    /// how far the faces are pushed out from the pieces
+    face_spacing: f32,
+    /// whether to split quads up so ones that cross each other sort better
+    split_quads: bool,
+    cell_culling: CellCulling,
+    /// the sides to draw when culling `CellCulling::Selected`
+    shown_sides: HashSet<Side>,
+    /// how far the cells are pushed apart in 4d and up
+    explode: f32,
+    /// whether to shade quads by how much they face the light
+    shading: bool,
+    /// how much far cells fade into the background in 4d and up
+    fog: f32,
+    /// how much each quad is shrunk to leave gaps around it
+    gap: f32,
+    /// the default and isometric views, then ones the user saved
+    view_presets: Vec<(String, ViewState)>,
+    ui_view_preset_name: String,
+    /// whether to line the view up with the axes when a drag ends
+    snap_view: bool,
+    /// which hidden axis ctrl dragging turns towards, in 6d and up
+    drag_hidden: usize,
+    view_transition: Option<ViewTransition>,
+    /// whether puzzle turns rotate the view instead of moving the stickers
+    rotate_view: bool,
+    /// how the view has been rotated by puzzle turns.
+    /// sides on screen are `orientation` of the sides of the puzzle.
+    orientation: Symmetry,
+    layout: Layout2d,
+    /// where the labels for the sides go
+    /// the centers if odd and offset in the positive direction if even
+    side_positions: HashMap<Side, Piece>,
+    turn_builder: TurnBuilder,
+    clicked_pieces: HashSet<Piece>,
+    internal_color: Color32,
+    internal_format: StickerFormat,
+    hovered_format: StickerFormatBuilder,
+    clicked_format: StickerFormatBuilder,
+    gripped_format: StickerFormatBuilder,
+    /// for the axes the turn being built can go to
+    grip_axis_format: StickerFormatBuilder,
+    default_filter_format: StickerFormat,
+    default_no_filter_format: StickerFormat,
+    filter_sequence: FilterSequence,
+    filter_stage: Option<usize>,
+    ui_dim: usize,
+    ui_cuts: Vec<i16>,
+    /// the turns that scrambled the puzzle
+    scramble: Vec<Turn>,
+    /// the turns made since the scramble
+    history: Vec<Turn>,
+    /// `history` simplified
+    simplified: SimplifiedTurns,
+    macros: MacroLibrary,
+    /// the colors the stickers are drawn with
+    color_scheme: ColorScheme,
+    color_schemes: ColorSchemes,
+    ui_color_scheme_name: String,
+    /// whether to draw each sticker's side letter on it
+    sticker_labels: bool,
+    keybinds: Keybinds,
+    timer: Timer,
+    /// the last autosave, to know when to save again
+    autosaved: String,
+    autosave_time: f64,
+    /// the time of the last frame, since `on_exit` doesn't get one
+    time: f64,
+    /// a save from last time, until the user picks whether to carry on with it
+    resume: Option<SavedState>,
+    /// messages shown for a while, with when they were first drawn
+    toasts: Vec<(String, Option<f64>)>,
+    /// what's in the settings file, to know when to save it
+    saved_settings: String,
+    settings_save_time: f64,
+    /// the turns made since recording started
+    recording: Option<Vec<Turn>>,
+    ui_macro_name: String,
+    ui_alg: String,
+    ui_alg_a: String,
+    ui_alg_b: String,
+    /// cleared whenever `ui_alg` changes
+    alg_preview: Option<Result<AlgPreview, String>>,
+    /// whether to highlight the pieces the algorithm moves
+    ui_alg_highlight: bool,
+    /// the axis on screen to mirror algorithms and macros across
+    ui_mirror_axis: Axis,
+    /// whether clicking stickers paints them, instead of turning being allowed
+    painting: bool,
+    /// the color to paint, or None to cycle through the colors
+    paint_side: Option<Side>,
+    /// why the painted stickers can't be solved, cleared when they change
+    paint_problems: Option<Vec<String>>,
+    /// a pasted `Puzzle::to_state_string`
+    ui_state: String,
+    preview_format: StickerFormatBuilder,
+    /// built the first time it's needed, on the thread that's solving
+    solver: std::sync::Arc<std::sync::OnceLock<Result<Solver, String>>>,
+    /// the puzzle the solution is for, and the solution
+    solution: Option<(Puzzle, Job<Solution>)>,
+    /// the pieces to get hints for solving
+    hint_filter: Filter,
+    /// the puzzle the hint is for, and the hint
+    hint: Option<(Puzzle, Job<Result<Hint, String>>)>,
+    /// for the pieces the hint turns
+    hint_format: StickerFormatBuilder,
+    /// for the sides the hint turns from and to
+    hint_axis_format: StickerFormatBuilder,
+}
+impl App {
+    /// the most quads `split_quads` splits, since checking which cross is slow
+    const MAX_SPLIT_QUADS: usize = 5000;
+    /// where shading light comes from, after rotating: the upper left, in front
+    const LIGHT: na::Vector3<f32> = na::Vector3::new(-1.0, 1.0, 2.0);
+    /// how bright quads facing away from the light are
+    const AMBIENT: f32 = 0.6;
+
+    #[inline(never)]
+    fn new(shape: &[Cut]) -> Self {
+        assert!(!shape.is_empty(), "dimension should be greater than 0");
+        assert!(
+            shape.iter().all(|cut| cut.0 > 0),
+            "side should be greater than 0"
+        );
+        // println!("{:?}", Position::all(shape).collect::<Vec<_>>());
+        // panic!();
+
+        let start = std::time::Instant::now();
+        let puzzle = Puzzle::new(shape);
+        println!("puzzle gen in {:?}", start.elapsed());
+
+        let start = std::time::Instant::now();
+        // let layout = Layout::TwoD(Layout2d::new(shape));
+        let layout = Layout2d::new(shape);
+        println!("layout gen in {:?}", start.elapsed());
+        // if let Layout::TwoD(layout) = &layout {
+        //     for (pos, xy) in layout.mapping.iter() {
+        //         println!(
+        //             "{:?} -> {:?}",
+        //             pos.iter().map(|c| c.0).collect::<Vec<_>>(),
+        //             xy,
+        //         );
+        //     }
+        // } else {
+        //     unreachable!()
+        // }
+
+        #[inline(never)]
+        fn get_side_positions(shape: &[Cut]) -> HashMap<Side, Piece> {
+            let mut side_positions = HashMap::new();
+            for (axis, cut) in shape.iter().enumerate() {
+                {
+                    // positive
+                    let mut pos = shape
+                        .iter()
+                        .map(|cut| if cut.0 % 2 == 1 { Coord(0) } else { Coord(1) })
+                        .collect::<Vec<_>>();
+                    pos[axis] = Coord(cut.0 - 1);
+                    side_positions.insert(Side(axis as i16), Piece::new(shape, pos));
+                }
+                {
+                    // negative
+                    let mut pos = shape
+                        .iter()
+                        .map(|cut| if cut.0 % 2 == 1 { Coord(0) } else { Coord(1) })
+                        .collect::<Vec<_>>();
+                    pos[axis] = Coord(1 - cut.0);
+                    side_positions.insert(Side(!(axis as i16)), Piece::new(shape, pos));
+                }
+            }
+            side_positions
+        }
+        // println!("width: {}, height: {}", layout.width, layout.height);
+        // for (pos, xy) in &layout.mapping {
+        //     println!(
+        //         "{:?} -> {:?}",
+        //         pos.iter().map(|c| c.0).collect::<Vec<_>>(),
+        //         xy,
+        //     );
+        // }
+        App {
+            puzzle,
+            layout,
+            cam_nd_to_4d: CameraNdTo4d::new(shape.len()),
+            cam_4d_to_3d: Camera4dTo3d::new(),
+            cam_3d_to_2d: Camera3dTo2d::new(),
+            sticker_size: 1.0,
+            // in 3d the stickers sit on the pieces,
+            // but in 4d they need room to be seen between the cells
+            face_spacing: if shape.len() <= 3 { 0.0 } else { 2.0 },
+            split_quads: false,
+            cell_culling: CellCulling::Nearest,
+            shown_sides: (0..shape.len() as i16)
+                .flat_map(|axis| [Side::new(axis), !Side::new(axis)])
+                .collect(),
+            explode: 0.0,
+            shading: true,
+            fog: 0.0,
+            gap: 0.0,
+            view_presets: vec![
+                ("default".into(), ViewState::default(shape.len())),
+                ("isometric".into(), ViewState::isometric(shape.len())),
+            ],
+            ui_view_preset_name: String::new(),
+            snap_view: false,
+            drag_hidden: 0,
+            view_transition: None,
+            rotate_view: false,
+            orientation: Symmetry::identity(shape.len()),
+            side_positions: get_side_positions(shape),
+            turn_builder: TurnBuilder::new(shape),
+            clicked_pieces: HashSet::new(),
+            internal_color: Color32::DARK_GRAY,
+            internal_format: StickerFormat {
+                outline_color: Color32::BLACK,
+                outline_width: 0.05,
+                sticker_scale: 1.0,
+                sticker_opacity: 1.0,
+            },
+            hovered_format: StickerFormatBuilder {
+                outline_color: Some(Color32::WHITE),
+                outline_width: Some(0.1),
+                sticker_scale: None,
+                sticker_opacity: None,
+            },
+            clicked_format: StickerFormatBuilder {
+                outline_color: Some(Color32::LIGHT_GRAY),
+                outline_width: Some(0.1),
+                sticker_scale: None,
+                sticker_opacity: None,
+            },
+            gripped_format: StickerFormatBuilder {
+                outline_color: Some(Color32::GRAY),
+                outline_width: Some(0.05),
+                sticker_scale: None,
+                sticker_opacity: None,
+            },
+            grip_axis_format: StickerFormatBuilder {
+                outline_color: Some(Color32::WHITE),
+                outline_width: Some(0.2),
+                sticker_scale: None,
+                sticker_opacity: None,
+            },
+            default_filter_format: StickerFormat {
+                outline_color: Color32::BLACK,
+                outline_width: 0.05,
+                sticker_scale: 0.7,
+                sticker_opacity: 0.5,
+            },
+            default_no_filter_format: StickerFormat {
+                outline_color: Color32::BLACK,
+                outline_width: 0.05,
+                sticker_scale: 1.0,
+                sticker_opacity: 1.0,
+            },
+            filter_sequence: FilterSequence(Vec::new()),
+            filter_stage: None,
+            ui_dim: shape.len(),
+            ui_cuts: shape.iter().map(|c| c.0).collect(),
+            scramble: Vec::new(),
+            history: Vec::new(),
+            simplified: SimplifiedTurns::default(),
+            macros: MacroLibrary::load(),
+            color_scheme: ColorScheme::builtin().swap_remove(0),
+            color_schemes: ColorSchemes::load(),
+            ui_color_scheme_name: String::new(),
+            sticker_labels: false,
+            keybinds: Keybinds::new(),
+            timer: Timer::new(),
+            autosaved: String::new(),
+            autosave_time: 0.0,
+            time: 0.0,
+            resume: None,
+            toasts: Vec::new(),
+            saved_settings: String::new(),
+            settings_save_time: 0.0,
+            recording: None,
+            ui_macro_name: String::new(),
+            ui_alg: String::new(),
+            ui_alg_a: String::new(),
+            ui_alg_b: String::new(),
+            alg_preview: None,
+            ui_alg_highlight: true,
+            ui_mirror_axis: Axis::new(0),
+            painting: false,
+            paint_side: None,
+            paint_problems: None,
+            ui_state: String::new(),
+            preview_format: StickerFormatBuilder {
+                outline_color: Some(Color32::GOLD),
+                outline_width: Some(0.15),
+                sticker_scale: None,
+                sticker_opacity: None,
+            },
+            solver: Default::default(),
+            solution: None,
+            hint_filter: Filter {
+                terms: vec![FilterTerm::new()],
+                format: StickerFormatBuilder::NONE,
+            },
+            hint: None,
+            hint_format: StickerFormatBuilder {
+                outline_color: Some(Color32::LIGHT_BLUE),
+                outline_width: Some(0.15),
+                sticker_scale: None,
+                sticker_opacity: None,
+            },
+            hint_axis_format: StickerFormatBuilder {
+                outline_color: Some(Color32::LIGHT_BLUE),
+                outline_width: Some(0.3),
+                sticker_scale: None,
+                sticker_opacity: None,
+            },
+        }
+    }
+
+    /// starts over with the shape from the ui, which throws away a pending resume
+    fn rebuild(&mut self) {
+        let shape: Vec<Cut> = self.ui_cuts[..self.ui_dim]
+            .iter()
+            .map(|&c| Cut(c))
+            .collect();
+        let ui_dim = self.ui_dim;
+        let ui_cuts = self.ui_cuts.clone();
+        let settings = self.settings();
+        let old_dim = self.puzzle.shape.len();
+        *self = App::new(&shape);
+        self.ui_dim = ui_dim;
+        self.ui_cuts = ui_cuts;
+        let face_spacing = self.face_spacing;
+        self.apply_settings(&settings);
+        // the default is different in 3d and 4d
+        if (old_dim <= 3) != (shape.len() <= 3) {
+            self.face_spacing = face_spacing;
+        }
+    }
+
+    /// changes to the puzzle would be thrown away by resuming, so they wait for a pick
+    fn check_resumed(&self) -> Result<(), TurnError> {
+        match self.resume {
+            Some(_) => Err(TurnError::Disallowed("pick whether to resume first")),
+            None => Ok(()),
+        }
+    }
+
+    fn turn(&mut self, turn: &Turn) -> Result<(), TurnError> {
+        self.check_resumed()?;
+        if self.painting {
+            return Err(TurnError::Disallowed("stop painting stickers first"));
+        }
+        self.puzzle.turn(turn)?;
+        self.history.push(turn.clone());
+        self.simplified.push(&self.puzzle.shape, turn);
+        if let Some(recording) = &mut self.recording {
+            recording.push(turn.clone());
+        }
+        Ok(())
+    }
+
+    /// does a turn where the sides are named by where they are on screen.
+    /// if `rotate_view`, puzzle turns rotate the view instead of moving stickers.
+    fn turn_on_screen(&mut self, turn: &Turn, time: f64) -> Result<(), TurnError> {
+        self.puzzle.check_axes(turn)?;
+        let turn = self.orientation.inverse().turn(turn);
+        match &turn {
+            Turn::Puzzle(puzzle_turn) if self.rotate_view => {
+                if puzzle_turn.from == puzzle_turn.to {
+                    return Err(TurnError::UndefinedPlane);
+                }
+                let symmetry = puzzle_turn.symmetry(&self.puzzle.shape);
+                self.orientation = self.orientation.compose(&symmetry);
+                // start from where any running transition is going
+                let view = match &self.view_transition {
+                    Some(transition) => transition.to.clone(),
+                    None => self.view(),
+                };
+                self.animate_view(view.rotated(&symmetry), time);
+                Ok(())
+            }
+            _ => self.turn(&turn),
+        }
+    }
+
+    /// for changes that turns don't lead to, like painting, so the turns so far are forgotten
+    fn forget_turns(&mut self) -> Result<(), TurnError> {
+        self.check_resumed()?;
+        self.scramble.clear();
+        self.history.clear();
+        self.simplified = SimplifiedTurns::default();
+        self.timer = Timer::new();
+        self.paint_problems = None;
+        Ok(())
+    }
+
+    /// reflects the puzzle across `axis` on screen
+    fn mirror(&mut self, axis: Axis) -> Result<(), TurnError> {
+        if self.recording.is_some() {
+            return Err(TurnError::Disallowed("stop recording first"));
+        }
+        self.forget_turns()?;
+        self.puzzle.mirror(self.mirror_axis(axis));
+        Ok(())
+    }
+
+    /// paints `sticker` with `paint_side`, or the next color if cycling
+    fn paint(&mut self, sticker: &Sticker, backwards: bool) {
+        if let Err(err) = self.forget_turns() {
+            self.toast(format!("can't paint: {err}"));
+            return;
+        }
+        let dim = self.puzzle.shape.len() as i16;
+        let sides = (0..dim).flat_map(|i| [Side(i), !Side(i)]).collect_vec();
+        let color = self.paint_side.unwrap_or_else(|| {
+            let i = sides
+                .iter()
+                .position(|side| *side == self.puzzle.stickers[sticker])
+                .unwrap();
+            let step = if backwards { sides.len() - 1 } else { 1 };
+            sides[(i + step) % sides.len()]
+        });
+        self.puzzle.stickers.insert(sticker.clone(), color);
+    }
+
+    fn ui_paint(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.painting, "paint stickers")
+            .on_hover_text("click to paint, right click to cycle backwards");
+        ui.horizontal_wrapped(|ui| {
+            ui.selectable_value(&mut self.paint_side, None, "cycle");
+            let dim = self.puzzle.shape.len() as i16;
+            for side in (0..dim).flat_map(|i| [Side(i), !Side(i)]) {
+                let color = self.color_scheme.color(side);
+                let text_color = if color.intensity() > 0.5 {
+                    Color32::BLACK
+                } else {
+                    Color32::WHITE
+                };
+                let text = egui::RichText::new(side.name().to_string())
+                    .color(text_color)
+                    .background_color(color);
+                ui.selectable_value(&mut self.paint_side, Some(side), text);
+            }
+        });
+        if ui.button("reset to solved").clicked() {
+            match self.forget_turns() {
+                Ok(()) => self.puzzle = Puzzle::new(&self.puzzle.shape),
+                Err(err) => self.toast(format!("can't reset: {err}")),
+            }
+        }
+        let problems = self
+            .paint_problems
+            .get_or_insert_with(|| self.puzzle.problems());
+        if problems.is_empty() {
+            ui.label("no problems found");
+        }
+        for problem in problems {
+            ui.label(problem.as_str());
+        }
+    }
+
+    /// rebuilds if the shape is different
+    fn load_state(&mut self, text: &str) -> Result<(), String> {
+        self.check_resumed().map_err(|err| err.to_string())?;
+        let puzzle = Puzzle::from_state_string(text)?;
+        if puzzle.shape != self.puzzle.shape {
+            self.ui_dim = puzzle.shape.len();
+            self.ui_cuts = puzzle.shape.iter().map(|cut| cut.0).collect();
+            self.rebuild();
+        }
+        self.puzzle = puzzle;
+        self.scramble.clear();
+        self.history.clear();
+        self.simplified = SimplifiedTurns::default();
+        self.timer = Timer::new();
+        self.paint_problems = None;
+        Ok(())
+    }
+
+    fn ui_state(&mut self, ui: &mut egui::Ui) {
+        if ui.button("copy").clicked() {
+            let state = self.puzzle.to_state_string();
+            ui.ctx().copy_text(state);
+            self.toast("copied the state".into());
+        }
+        ui.add(
+            egui::TextEdit::multiline(&mut self.ui_state)
+                .hint_text("paste a state here")
+                .desired_rows(2),
+        );
+        if ui.button("load").clicked() {
+            let text = std::mem::take(&mut self.ui_state);
+            if let Err(err) = self.load_state(&text) {
+                self.toast(format!("can't load the state: {err}"));
+                self.ui_state = text;
+            }
+        }
+    }
+
+    /// `axis` on screen, on the puzzle
+    fn mirror_axis(&self, axis: Axis) -> Axis {
+        self.orientation.inverse().side(axis.into_side()).axis()
+    }
+
+    /// picks `ui_mirror_axis`
+    fn ui_mirror_axis(&mut self, ui: &mut egui::Ui) {
+        egui::ComboBox::from_id_salt("mirror axis")
+            .selected_text(self.ui_mirror_axis.name().to_string())
+            .show_ui(ui, |ui| {
+                for axis in (0..self.puzzle.shape.len()).map(Axis::from_usize) {
+                    ui.selectable_value(&mut self.ui_mirror_axis, axis, axis.name().to_string());
+                }
+            })
+            .response
+            .on_hover_text("the axis on screen to mirror across");
+    }
+
+    /// plays the macro with the currently gripped side,
+    /// or where it was recorded if no side is gripped.
+    /// if `mirrored`, plays the other handed version, across `ui_mirror_axis`
+    fn play_macro(&mut self, i: usize, mirrored: bool) {
+        let mirror_axis = self.mirror_axis(self.ui_mirror_axis);
+        let m = &if mirrored {
+            self.macros.0[i].mirrored(mirror_axis)
+        } else {
+            self.macros.0[i].clone()
+        };
+        let grip = match self.turn_builder {
+            TurnBuilder::Side {
+                side: Some(side), ..
+            } => self.orientation.inverse().side(side),
+            _ => m.grip,
+        };
+        let Some(turns) = m.turns_at(&self.puzzle.shape, grip) else {
+            self.toast(format!("can't play {:?} on {}", m.name, grip.name()));
+            return;
+        };
+        for turn in &turns {
+            if let Err(err) = self.turn(turn) {
+                self.toast(format!("can't do {turn}: {err}"));
+                return;
+            }
+        }
+        println!("solved: {}", self.puzzle.is_solved());
+    }
+
+    /// the pieces to draw with `preview_format`
+    fn previewed_pieces(&self) -> Option<&HashSet<Piece>> {
+        match &self.alg_preview {
+            Some(Ok(preview)) if self.ui_alg_highlight => Some(&preview.moved_pieces),
+            _ => None,
+        }
+    }
+
+    /// forgets the hint, stopping its search if it's still going
+    fn cancel_hint(&mut self) {
+        if let Some((_, job)) = self.hint.take() {
+            job.cancel();
+        }
+    }
+
+    /// the hint that's still valid for the puzzle.
+    /// this compares every sticker, so it's checked once per frame, not per sticker.
+    fn current_hint(&self) -> Option<&Hint> {
+        match &self.hint {
+            Some((puzzle, hint)) if *puzzle == self.puzzle => hint.get()?.as_ref().ok(),
+            _ => None,
+        }
+    }
+
+    /// the format for a piece from the algorithm preview and hint, if any
+    /// for pieces that move with the turn being built,
+    /// and for the axes it can go to once the from axis is chosen
+    fn grip_format(&self, piece: &Piece) -> Option<&StickerFormatBuilder> {
+        let dim = self.puzzle.shape.len();
+        // the builder names sides by where they are on screen
+        let to_puzzle = self.orientation.inverse();
+        let to_candidate = |excluded: &[Axis]| {
+            (0..dim)
+                .map(Axis::from_usize)
+                .filter(|axis| !excluded.contains(axis))
+                .any(|axis| self.side_positions.get(&axis.into_side()) == Some(piece))
+        };
+        match &self.turn_builder {
+            TurnBuilder::Side {
+                layers,
+                side: Some(side),
+                from,
+                ..
+            } => {
+                let side = to_puzzle.side(*side);
+                if let Some(from) = from
+                    && to_candidate(&[side.axis(), to_puzzle.side(from.into_side()).axis()])
+                {
+                    return Some(&self.grip_axis_format);
+                }
+                let axis = side.axis().into_usize();
+                let slab = self.puzzle.shape[axis].slab(piece.0.0[axis]);
+                layers
+                    .slabs(&self.puzzle.shape, side)
+                    .contains(&slab)
+                    .then_some(&self.gripped_format)
+            }
+            TurnBuilder::Puzzle {
+                from: Some(from), ..
+            } => to_candidate(&[to_puzzle.side(from.into_side()).axis()])
+                .then_some(&self.grip_axis_format),
+            _ => None,
+        }
+    }
+
+    /// `hint` is `current_hint`
+    fn highlight_format<'a>(
+        &'a self,
+        piece: &Piece,
+        hint: Option<&'a Hint>,
+    ) -> Option<&'a StickerFormatBuilder> {
+        if let Some(pieces) = self.previewed_pieces()
+            && pieces.contains(piece)
+        {
+            return Some(&self.preview_format);
+        }
+        if let Some(format) = self.grip_format(piece) {
+            return Some(format);
+        }
+        let hint = hint?;
+        if let Turn::Side(SideTurn { from, to, .. }) | Turn::Puzzle(PuzzleTurn { from, to }) =
+            &hint.turns[0]
+            && [from, to]
+                .iter()
+                .any(|axis| self.side_positions.get(&axis.into_side()) == Some(piece))
+        {
+            return Some(&self.hint_axis_format);
+        }
+        hint.moved_pieces
+            .contains(piece)
+            .then_some(&self.hint_format)
+    }
+
+    fn ui_algorithm(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("A");
+            ui.text_edit_singleline(&mut self.ui_alg_a);
+        });
+        ui.horizontal(|ui| {
+            ui.label("B");
+            ui.text_edit_singleline(&mut self.ui_alg_b);
+        });
+        ui.horizontal(|ui| {
+            let (a, b) = (self.ui_alg_a.trim(), self.ui_alg_b.trim());
+            if ui.button("[A, B]").clicked() {
+                self.ui_alg = format!("[{a}, {b}]");
+                self.alg_preview = None;
+            }
+            if ui.button("[A: B]").clicked() {
+                self.ui_alg = format!("[{a}: {b}]");
+                self.alg_preview = None;
+            }
+        });
+        if ui.text_edit_singleline(&mut self.ui_alg).changed() {
+            self.alg_preview = None;
+        }
+        ui.horizontal(|ui| {
+            if ui.button("mirror").clicked()
+                && let Ok(turns) = Turn::parse_seq(&self.ui_alg)
+            {
+                let symmetry = Symmetry::mirror(
+                    self.puzzle.shape.len(),
+                    self.mirror_axis(self.ui_mirror_axis),
+                );
+                let turns = turns.iter().map(|turn| symmetry.turn(turn));
+                self.ui_alg = Turn::format_seq(&turns.collect::<Vec<_>>());
+                self.alg_preview = None;
+            }
+            self.ui_mirror_axis(ui);
+        });
+        let preview = self.alg_preview.get_or_insert_with(|| {
+            Turn::parse_seq(&self.ui_alg)
+                .map_err(|err| err.to_string())
+                .and_then(|turns| AlgPreview::new(&self.puzzle.shape, turns))
+        });
+        let preview = match preview {
+            Ok(preview) => preview,
+            Err(err) => {
+                ui.label(err.as_str());
+                return;
+            }
+        };
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{} turns, {} moves",
+                preview.turns.len(),
+                Turn::count_moves(&self.puzzle.shape, &preview.turns)
+            ))
+            .on_hover_text(Turn::format_seq(&preview.turns));
+            if ui.button("simplify").clicked() {
+                self.ui_alg = Turn::format_seq(&Turn::simplify(&self.puzzle.shape, &preview.turns));
+            }
+        });
+
+        const MAX_PIECES_SHOWN: usize = 20;
+        let mut pieces = preview
+            .moved_pieces
+            .iter()
+            .map(|piece| piece.name(&self.puzzle.shape))
+            .sorted()
+            .take(MAX_PIECES_SHOWN)
+            .join(" ");
+        if preview.moved_pieces.len() > MAX_PIECES_SHOWN {
+            pieces.push_str(" ...");
+        }
+        ui.label(format!(
+            "moves {} stickers on {} pieces",
+            preview.permutation.sources.len(),
+            preview.moved_pieces.len(),
+        ))
+        .on_hover_text(pieces);
+        ui.label(&preview.cycle_structure);
+        let order = |order: Option<u64>| order.map_or("huge".into(), |order| order.to_string());
+        ui.label(format!(
+            "order {}, looks solved after {}",
+            order(preview.order),
+            order(preview.solved_order),
+        ));
+        for (orbit, odd) in &preview.orbit_parities {
+            ui.label(format!("{orbit}: {}", if *odd { "odd" } else { "even" }));
+        }
+        ui.collapsing("cycles", |ui| {
+            const MAX_CYCLES_SHOWN: usize = 50;
+            egui::ScrollArea::vertical()
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    for (name, cycles) in [
+                        ("pieces", &preview.piece_cycles),
+                        ("stickers", &preview.sticker_cycles),
+                    ] {
+                        ui.label(format!("{} {name} cycles", cycles.len()));
+                        for cycle in cycles.iter().take(MAX_CYCLES_SHOWN) {
+                            ui.monospace(cycle);
+                        }
+                        if cycles.len() > MAX_CYCLES_SHOWN {
+                            ui.label("...");
+                        }
+                    }
+                });
+        });
+        ui.checkbox(&mut self.ui_alg_highlight, "highlight");
+        if ui.button("apply").clicked() {
+            for turn in preview.turns.clone() {
+                if let Err(err) = self.turn(&turn) {
+                    self.toast(format!("can't do {turn}: {err}"));
+                    break;
+                }
+            }
+            println!("solved: {}", self.puzzle.is_solved());
+        }
+    }
+
+    fn ui_solver(&mut self, ui: &mut egui::Ui) {
+        if ui.button("solve").clicked() {
+            let puzzle = self.puzzle.clone();
+            let solver = self.solver.clone();
+            let job = Job::spawn(move |_| {
+                // searching for a solution that doesn't exist would never finish
+                let problems = puzzle.problems();
+                if !problems.is_empty() {
+                    return Err(format!("can't be solved:\n{}", problems.join("\n")));
+                }
+                solver
+                    .get_or_init(|| Solver::new(&puzzle.shape))
+                    .as_ref()
+                    .map_err(|err| err.clone())
+                    .and_then(|solver| solver.solve(&puzzle))
+            });
+            self.solution = Some((self.puzzle.clone(), job));
+        }
+        let Some((puzzle, solution)) = &self.solution else {
+            return;
+        };
+        let turns = match solution.get() {
+            None => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("solving");
+                });
+                return;
+            }
+            Some(Ok(turns)) => turns,
+            Some(Err(err)) => {
+                ui.label(err.as_str());
+                return;
+            }
+        };
+        if *puzzle != self.puzzle {
+            ui.label("the puzzle has changed since solving");
+            return;
+        }
+        ui.label(format!(
+            "{} turns, {} moves",
+            turns.len(),
+            Turn::count_moves(&self.puzzle.shape, turns)
+        ));
+        ui.monospace(Turn::format_seq(turns));
+        let turns = turns.clone();
+        ui.horizontal(|ui| {
+            if ui.button("step").clicked()
+                && let Some(turn) = turns.first()
+            {
+                if let Err(err) = self.turn(turn) {
+                    println!("can't do {turn}: {err:?}");
+                    return;
+                }
+                self.solution = Some((self.puzzle.clone(), Job::done(Ok(turns[1..].to_vec()))));
+            }
+            if ui.button("apply").clicked() {
+                for turn in &turns {
+                    if let Err(err) = self.turn(turn) {
+                        println!("can't do {turn}: {err:?}");
+                        break;
+                    }
+                }
+                println!("solved: {}", self.puzzle.is_solved());
+            }
+            if ui.button("to algorithm").clicked() {
+                self.ui_alg = Turn::format_seq(&turns);
+                self.alg_preview = None;
+            }
+        });
+    }
+
+    fn ui_hint(&mut self, ui: &mut egui::Ui) {
+        ui.label("pieces to solve");
+        let dim = self.puzzle.shape.len() as i16;
+        let term = &mut self.hint_filter.terms[0];
+        let mut changed = false;
+        for (label, sides) in [
+            ("must have", &mut term.must_have),
+            ("can't have", &mut term.cant_have),
+        ] {
+            ui.horizontal(|ui| {
+                ui.label(label);
+                for side in (0..dim).flat_map(|axis| [Side::new(axis), !Side::new(axis)]) {
+                    let mut checked = sides.contains(&side);
+                    if ui.checkbox(&mut checked, side.name().to_string()).changed() {
+                        if checked {
+                            sides.insert(side);
+                        } else {
+                            sides.remove(&side);
+                        }
+                        changed = true;
+                    }
+                }
+            });
+        }
+        if changed {
+            self.cancel_hint();
+        }
+        if ui.button("hint").clicked() {
+            self.cancel_hint();
+            let puzzle = self.puzzle.clone();
+            let filter = self.hint_filter.clone();
+            let job = Job::spawn(move |cancelled| Hint::new(&puzzle, &filter, cancelled));
+            self.hint = Some((self.puzzle.clone(), job));
+        }
+        match &self.hint {
+            None => {}
+            Some((puzzle, _)) if *puzzle != self.puzzle => {
+                ui.label("the puzzle has changed since the hint");
+            }
+            Some((_, hint)) => match hint.get() {
+                None => {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("searching");
+                    });
+                }
+                Some(Err(err)) => {
+                    ui.label(err.as_str());
+                }
+                Some(Ok(hint)) => {
+                    ui.label(format!(
+                        "{} solves {} of {} pieces, {} now",
+                        Turn::format_seq(&hint.turns),
+                        hint.solved.1,
+                        hint.total,
+                        hint.solved.0,
+                    ));
+                }
+            },
+        }
+    }
+
+    fn view(&self) -> ViewState {
+        ViewState::get(&self.cam_nd_to_4d, &self.cam_4d_to_3d, &self.cam_3d_to_2d)
+    }
+
+    /// smoothly changes the view to `to`, starting at `time`
+    fn animate_view(&mut self, to: ViewState, time: f64) {
+        self.view_transition = Some(ViewTransition {
+            from: self.view(),
+            to,
+            start: time,
+        });
+    }
+
+    /// lambert shading of a quad facing `normal`, after rotating
+    fn shade(color: Color32, normal: &na::Vector3<f32>) -> Color32 {
+        let lambert = normal.normalize().dot(&Self::LIGHT.normalize()).max(0.0);
+        let brightness = Self::AMBIENT + (1.0 - Self::AMBIENT) * lambert;
+        let [r, g, b, a] = color.to_array();
+        let shade = |c: u8| (c as f32 * brightness).round() as u8;
+        Color32::from_rgba_premultiplied(shade(r), shade(g), shade(b), a)
+    }
+
+    /// the sides whose cells aren't drawn
+    fn culled_sides(&self) -> HashSet<Side> {
+        let dim = self.puzzle.shape.len();
+        if dim <= 3 {
+            return HashSet::new();
+        }
+        // the middle of each side's cell, in 4d
+        let centers = (0..dim as i16)
+            .flat_map(|axis| [Side::new(axis), !Side::new(axis)])
+            .map(|side| {
+                let mut pos = vec![0.0; dim];
+                pos[side.axis().into_usize()] = if side.is_positive() { 1.0 } else { -1.0 };
+                let pos = if dim > 4 {
+                    self.cam_nd_to_4d.project(&pos)
+                } else {
+                    *pos.as_array().unwrap()
+                };
+                (side, pos)
+            });
+        match self.cell_culling {
+            CellCulling::None => HashSet::new(),
+            CellCulling::Nearest => centers
+                .min_by(|(_, a), (_, b)| {
+                    let depth = |pos| self.cam_4d_to_3d.project(pos).1;
+                    depth(a).total_cmp(&depth(b))
+                })
+                .map(|(side, _)| side)
+                .into_iter()
+                .collect(),
+            CellCulling::FacingAway => centers
+                .filter(|(_, pos)| self.cam_4d_to_3d.is_outside(pos))
+                .map(|(side, _)| side)
+                .collect(),
+            CellCulling::Selected => centers
+                .map(|(side, _)| side)
+                .filter(|side| !self.shown_sides.contains(side))
+                .collect(),
+        }
+    }
+
+    fn ui_view(&mut self, ui: &mut egui::Ui) {
+        let dim = self.puzzle.shape.len();
+        let time = ui.input(|i| i.time);
+        ui.horizontal(|ui| {
+            ui.label("reset");
+            let default = ViewState::default(dim).rotated(&self.orientation);
+            if ui.button("3d").clicked() {
+                let view = ViewState {
+                    rot_3d: default.rot_3d,
+                    ..self.view()
+                };
+                self.animate_view(view, time);
+            }
+            if dim >= 4 && ui.button("4d").clicked() {
+                let view = ViewState {
+                    rot_4d: default.rot_4d,
+                    ..self.view()
+                };
+                self.animate_view(view, time);
+            }
+            if dim >= 5 && ui.button("nd").clicked() {
+                self.cam_nd_to_4d.angles = CameraNdTo4d::new(dim).angles;
+                let view = ViewState {
+                    rot_nd: default.rot_nd,
+                    ..self.view()
+                };
+                self.animate_view(view, time);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("face on");
+            for side in (0..dim as i16).flat_map(|axis| [Side::new(axis), !Side::new(axis)]) {
+                if ui.button(side.name().to_string()).clicked() {
+                    let view = ViewState::face_on(dim, side).rotated(&self.orientation);
+                    self.animate_view(view, time);
+                }
+            }
+        });
+        let mut to_show = None;
+        let mut to_delete = None;
+        for (i, (name, view)) in self.view_presets.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button(name).clicked() {
+                    to_show = Some(view.clone());
+                }
+                // the first two are built in
+                if i >= 2 && ui.button("delete").clicked() {
+                    to_delete = Some(i);
+                }
+            });
+        }
+        if let Some(view) = to_show {
+            self.animate_view(view.rotated(&self.orientation), time);
+        }
+        if let Some(i) = to_delete {
+            self.view_presets.remove(i);
+        }
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.ui_view_preset_name);
+            if ui.button("save view").clicked() && !self.ui_view_preset_name.is_empty() {
+                let name = std::mem::take(&mut self.ui_view_preset_name);
+                // saved without the puzzle turns, like the built in ones
+                let view = self.view().rotated(&self.orientation.inverse());
+                self.view_presets.push((name, view));
+            }
+        });
+        ui.checkbox(&mut self.snap_view, "snap on release");
+        ui.checkbox(&mut self.split_quads, "split quads")
+            .on_hover_text(format!(
+                "fewer drawing glitches where stickers cross, but slower. \
+                only up to {} quads",
+                Self::MAX_SPLIT_QUADS
+            ));
+        ui.checkbox(&mut self.rotate_view, "rotate the view, not the stickers")
+            .on_hover_text("puzzle turns move the camera, and keys refer to sides on screen");
+        if dim >= 4 {
+            ui.add(egui::Slider::new(&mut self.cam_4d_to_3d.fov, 0.0..=90.0).text("4d fov"));
+        }
+        if dim >= 6 {
+            ui.add(
+                egui::Slider::new(&mut self.drag_hidden, 0..=dim - 5)
+                    .custom_formatter(|n, _| (n + 1.0).to_string())
+                    .text("hidden axis"),
+            )
+            .on_hover_text("which of the axes not shown in 4d ctrl dragging turns towards");
+        }
+        ui.add(egui::Slider::new(&mut self.cam_3d_to_2d.fov, 0.0..=90.0).text("3d fov"));
+        ui.add(egui::Slider::new(&mut self.sticker_size, 0.1..=1.5).text("sticker size"));
+        ui.add(egui::Slider::new(&mut self.face_spacing, 0.0..=4.0).text("face spacing"));
+        ui.add(egui::Slider::new(&mut self.gap, 0.0..=0.5).text("gap"));
+        ui.checkbox(&mut self.shading, "shading");
+        if dim >= 4 {
+            ui.add(egui::Slider::new(&mut self.fog, 0.0..=1.0).text("fog"));
+            ui.add(egui::Slider::new(&mut self.explode, 0.0..=2.0).text("explode"));
+            ui.horizontal(|ui| {
+                ui.label("cull");
+                ui.radio_value(&mut self.cell_culling, CellCulling::None, "none");
+                ui.radio_value(&mut self.cell_culling, CellCulling::Nearest, "nearest");
+                ui.radio_value(
+                    &mut self.cell_culling,
+                    CellCulling::FacingAway,
+                    "facing away",
+                )
+                .on_hover_text("the cells that would be drawn inside out");
+                ui.radio_value(&mut self.cell_culling, CellCulling::Selected, "selected");
+            });
+            if self.cell_culling == CellCulling::Selected {
+                ui.horizontal(|ui| {
+                    ui.label("show");
+                    for side in (0..dim as i16).flat_map(|axis| [Side::new(axis), !Side::new(axis)])
+                    {
+                        let mut shown = self.shown_sides.contains(&side);
+                        if ui.checkbox(&mut shown, side.name().to_string()).changed() {
+                            if shown {
+                                self.shown_sides.insert(side);
+                            } else {
+                                self.shown_sides.remove(&side);
+                            }
+                        }
+                    }
+                });
+            }
+        }
+        let mut changed = false;
+        for (axis, angle) in (4..dim).zip(&mut self.cam_nd_to_4d.angles) {
+            changed |= ui
+                .add(
+                    egui::Slider::new(angle, -std::f32::consts::PI..=std::f32::consts::PI)
+                        .text(format!("axis {} angle", Axis::from_usize(axis).name())),
+                )
+                .changed();
+        }
+        if changed {
+            self.cam_nd_to_4d.rebuild();
+        }
+    }
 
-    #[inline(never)]
-    fn new(shape: &[Cut]) -> Self {
-        assert!(!shape.is_empty(), "dimension should be greater than 0");
-        assert!(
-            shape.len() <= Self::MAX_DIM,
-            "dimension should be less than or equal to {}",
-            Self::MAX_DIM
+    fn settings_path() -> std::path::PathBuf {
+        config_dir().join("settings.txt")
+    }
+
+    /// `key = value` lines
+    fn settings(&self) -> String {
+        let mut ret = String::new();
+        let mut set = |key: &str, value: String| {
+            ret.push_str(&format!("{key} = {value}\n"));
+        };
+        set("shape", self.puzzle.shape.iter().map(|cut| cut.0).join("x"));
+        set("fov_4d", self.cam_4d_to_3d.fov.to_string());
+        set("fov_3d", self.cam_3d_to_2d.fov.to_string());
+        set("sticker_size", self.sticker_size.to_string());
+        set("face_spacing", self.face_spacing.to_string());
+        set("gap", self.gap.to_string());
+        set("explode", self.explode.to_string());
+        set("fog", self.fog.to_string());
+        set("shading", self.shading.to_string());
+        set("split_quads", self.split_quads.to_string());
+        set(
+            "cell_culling",
+            match self.cell_culling {
+                CellCulling::None => "none",
+                CellCulling::Nearest => "nearest",
+                CellCulling::FacingAway => "facing_away",
+                CellCulling::Selected => "selected",
+            }
+            .into(),
         );
-        assert!(
-            shape.iter().all(|cut| cut.0 > 0),
-            "side should be greater than 0"
+        set("snap_view", self.snap_view.to_string());
+        set("rotate_view", self.rotate_view.to_string());
+        set("sticker_labels", self.sticker_labels.to_string());
+        set("color_scheme", self.color_scheme.to_line());
+        set("internal_color", self.internal_color.to_hex());
+        set("internal_format", self.internal_format.to_setting());
+        set("hovered_format", self.hovered_format.to_setting());
+        set("clicked_format", self.clicked_format.to_setting());
+        set("gripped_format", self.gripped_format.to_setting());
+        set("grip_axis_format", self.grip_axis_format.to_setting());
+        set(
+            "default_filter_format",
+            self.default_filter_format.to_setting(),
         );
-        assert!(
-            shape.iter().all(|cut| cut.0 <= Self::MAX_LAYERS),
-            "side should be less than or equal to {}",
-            Self::MAX_LAYERS
+        set(
+            "default_no_filter_format",
+            self.default_no_filter_format.to_setting(),
         );
-        // println!("{:?}", Position::all(shape).collect::<Vec<_>>());
-        // panic!();
+        set(
+            "keys_positive_sides",
+            self.keybinds.pos_sides.iter().collect(),
+        );
+        set(
+            "keys_negative_sides",
+            self.keybinds.neg_sides.iter().collect(),
+        );
+        set("keys_axes", self.keybinds.axes.iter().collect());
+        set("key_puzzle", self.keybinds.puzzle.to_string());
+        ret
+    }
 
-        let start = std::time::Instant::now();
-        let puzzle = Puzzle::new(shape);
-        println!("puzzle gen in {:?}", start.elapsed());
+    /// the shape in `settings`, if it's a valid one
+    fn settings_shape(settings: &str) -> Option<Vec<Cut>> {
+        settings.lines().find_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            (key.trim() == "shape").then(|| Cut::parse_shape(value))?
+        })
+    }
 
-        let start = std::time::Instant::now();
-        // let layout = Layout::TwoD(Layout2d::new(shape));
-        let layout = Layout2d::new(shape);
-        println!("layout gen in {:?}", start.elapsed());
-        // if let Layout::TwoD(layout) = &layout {
-        //     for (pos, xy) in layout.mapping.iter() {
-        //         println!(
-        //             "{:?} -> {:?}",
-        //             pos.iter().map(|c| c.0).collect::<Vec<_>>(),
-        //             xy,
-        //         );
-        //     }
-        // } else {
-        //     unreachable!()
-        // }
+    /// skips lines that don't parse, and the shape, which needs a new `App`
+    fn apply_settings(&mut self, settings: &str) {
+        for line in settings.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let applied = line
+                .split_once('=')
+                .and_then(|(key, value)| self.apply_setting(key.trim(), value.trim()));
+            if applied.is_none() {
+                println!("couldn't parse setting {line:?}");
+            }
+        }
+        if !self.keybinds.is_valid() {
+            println!("keybinds use a key twice or one that can't be pressed, using the defaults");
+            self.keybinds = Keybinds::new();
+        }
+    }
 
-        #[inline(never)]
-        fn get_side_positions(shape: &[Cut]) -> HashMap<Side, Piece> {
-            let mut side_positions = HashMap::new();
-            for (axis, cut) in shape.iter().enumerate() {
-                {
-                    // positive
-                    let mut pos = shape
-                        .iter()
-                        .map(|cut| if cut.0 % 2 == 1 { Coord(0) } else { Coord(1) })
-                        .collect::<Vec<_>>();
-                    pos[axis] = Coord(cut.0 - 1);
-                    side_positions.insert(Side(axis as i16), Piece::new(shape, pos));
+    fn apply_setting(&mut self, key: &str, value: &str) -> Option<()> {
+        let float = |range: std::ops::RangeInclusive<f32>| {
+            value.parse::<f32>().ok().filter(|x| range.contains(x))
+        };
+        let keys = || Some(value.chars().collect::<Vec<_>>());
+        match key {
+            "shape" => {}
+            "fov_4d" => self.cam_4d_to_3d.fov = float(0.0..=90.0)?,
+            "fov_3d" => self.cam_3d_to_2d.fov = float(0.0..=90.0)?,
+            "sticker_size" => self.sticker_size = float(0.1..=1.5)?,
+            "face_spacing" => self.face_spacing = float(0.0..=4.0)?,
+            "gap" => self.gap = float(0.0..=0.5)?,
+            "explode" => self.explode = float(0.0..=2.0)?,
+            "fog" => self.fog = float(0.0..=1.0)?,
+            "shading" => self.shading = value.parse().ok()?,
+            "split_quads" => self.split_quads = value.parse().ok()?,
+            "cell_culling" => {
+                self.cell_culling = match value {
+                    "none" => CellCulling::None,
+                    "nearest" => CellCulling::Nearest,
+                    "facing_away" => CellCulling::FacingAway,
+                    "selected" => CellCulling::Selected,
+                    _ => return None,
                 }
-                {
-                    // negative
-                    let mut pos = shape
-                        .iter()
-                        .map(|cut| if cut.0 % 2 == 1 { Coord(0) } else { Coord(1) })
-                        .collect::<Vec<_>>();
-                    pos[axis] = Coord(1 - cut.0);
-                    side_positions.insert(Side(!(axis as i16)), Piece::new(shape, pos));
+            }
+            "snap_view" => self.snap_view = value.parse().ok()?,
+            "rotate_view" => self.rotate_view = value.parse().ok()?,
+            "sticker_labels" => self.sticker_labels = value.parse().ok()?,
+            "color_scheme" => {
+                // the tab got trimmed if there are no colors
+                self.color_scheme = ColorScheme::from_line(value)
+                    .or_else(|| ColorScheme::from_line(&format!("{value}\t")))?
+            }
+            "internal_color" => self.internal_color = Color32::from_hex(value).ok()?,
+            "internal_format" => self.internal_format = StickerFormat::from_setting(value)?,
+            "hovered_format" => self.hovered_format = StickerFormatBuilder::from_setting(value)?,
+            "clicked_format" => self.clicked_format = StickerFormatBuilder::from_setting(value)?,
+            "gripped_format" => self.gripped_format = StickerFormatBuilder::from_setting(value)?,
+            "grip_axis_format" => {
+                self.grip_axis_format = StickerFormatBuilder::from_setting(value)?
+            }
+            "default_filter_format" => {
+                self.default_filter_format = StickerFormat::from_setting(value)?
+            }
+            "default_no_filter_format" => {
+                self.default_no_filter_format = StickerFormat::from_setting(value)?
+            }
+            "keys_positive_sides" => self.keybinds.pos_sides = keys()?,
+            "keys_negative_sides" => self.keybinds.neg_sides = keys()?,
+            "keys_axes" => self.keybinds.axes = keys()?,
+            "key_puzzle" => {
+                let mut chars = value.chars();
+                self.keybinds.puzzle = chars.next().filter(|_| chars.next().is_none())?;
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+
+    /// the app as it was last time, or a 3x3x4x4 the first time
+    fn load() -> Self {
+        let settings = std::fs::read_to_string(Self::settings_path()).unwrap_or_default();
+        let shape =
+            Self::settings_shape(&settings).unwrap_or_else(|| vec![Cut(3), Cut(3), Cut(4), Cut(4)]);
+        let mut app = App::new(&shape);
+        app.apply_settings(&settings);
+        app.saved_settings = app.settings();
+        app.resume = SavedState::load().filter(|state| !state.puzzle.is_solved());
+        app
+    }
+
+    fn saved_state(&self, time: f64) -> SavedState {
+        SavedState {
+            puzzle: self.puzzle.clone(),
+            scramble: self.scramble.clone(),
+            history: self.history.clone(),
+            timer: (
+                self.timer.seconds(time),
+                self.timer.start.is_some(),
+                self.timer.done,
+            ),
+            orientation: self.orientation.clone(),
+            view: self.view(),
+        }
+    }
+
+    /// carries on from a save, keeping the settings
+    fn restore(&mut self, state: SavedState, time: f64) {
+        self.ui_dim = state.puzzle.shape.len();
+        self.ui_cuts = state.puzzle.shape.iter().map(|cut| cut.0).collect();
+        self.rebuild();
+        self.simplified = SimplifiedTurns::new(&state.puzzle.shape, &state.history);
+        self.puzzle = state.puzzle;
+        self.scramble = state.scramble;
+        self.history = state.history;
+        let (elapsed, running, done) = state.timer;
+        self.timer = Timer {
+            elapsed,
+            start: running.then_some(time),
+            done,
+        };
+        self.orientation = state.orientation;
+        state.view.set(
+            &mut self.cam_nd_to_4d,
+            &mut self.cam_4d_to_3d,
+            &mut self.cam_3d_to_2d,
+        );
+    }
+
+    /// saves the puzzle if it changed, every `SavedState::INTERVAL` seconds or if `now`
+    fn autosave(&mut self, time: f64, now: bool) {
+        // don't overwrite the save that might get resumed
+        if self.resume.is_some() || (!now && time - self.autosave_time < SavedState::INTERVAL) {
+            return;
+        }
+        self.autosave_time = time;
+        let text = self.saved_state(time).to_text();
+        // the timer changes the text while it's running, so compare without it
+        let without_timer = |text: &str| {
+            text.lines()
+                .filter(|line| !line.starts_with("timer"))
+                .join("\n")
+        };
+        if !now && without_timer(&text) == without_timer(&self.autosaved) {
+            return;
+        }
+        if let Err(err) = SavedState::save(&text) {
+            println!("couldn't autosave: {err}");
+        }
+        self.autosaved = text;
+    }
+
+    /// starts the timer on the first turn after a scramble, and stops it when solved
+    fn update_timer(&mut self, time: f64) {
+        if self.timer.start.is_none()
+            && !self.timer.done
+            && !self.scramble.is_empty()
+            && !self.history.is_empty()
+        {
+            self.timer.start = Some(time);
+        }
+        if let Some(start) = self.timer.start
+            && self.puzzle.is_solved()
+        {
+            self.timer.elapsed += time - start;
+            self.timer.start = None;
+            self.timer.done = true;
+        }
+    }
+
+    fn toast(&mut self, message: String) {
+        println!("{message}");
+        self.toasts.push((message, None));
+    }
+
+    /// draws the toasts at the bottom of the screen, fading them out
+    fn ui_toasts(&mut self, ctx: &egui::Context, time: f64) {
+        const TOAST_SECONDS: f64 = 4.0;
+        self.toasts
+            .retain(|(_, shown)| shown.is_none_or(|shown| time - shown < TOAST_SECONDS));
+        if self.toasts.is_empty() {
+            return;
+        }
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(egui::Align2::CENTER_BOTTOM, egui::Vec2::new(0.0, -40.0))
+            .interactable(false)
+            .show(ctx, |ui| {
+                for (message, shown) in &mut self.toasts {
+                    let age = time - *shown.get_or_insert(time);
+                    // fade out over the last second
+                    ui.set_opacity((TOAST_SECONDS - age).clamp(0.0, 1.0) as f32);
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.label(message.as_str());
+                    });
                 }
+            });
+    }
+
+    fn ui_resume(&mut self, ctx: &egui::Context, time: f64) {
+        let Some(state) = &self.resume else {
+            return;
+        };
+        let text = format!(
+            "carry on with the {} from last time? {} moves, {}",
+            state.puzzle.shape.iter().map(|cut| cut.0).join("x"),
+            Turn::count_moves(&state.puzzle.shape, &state.history),
+            Timer {
+                elapsed: state.timer.0,
+                start: None,
+                done: false,
+            }
+            .format(time),
+        );
+        let mut choice = None;
+        egui::Window::new("resume")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(text);
+                ui.horizontal(|ui| {
+                    if ui.button("resume").clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button("start over").clicked() {
+                        choice = Some(false);
+                    }
+                });
+            });
+        if let Some(resume) = choice {
+            let state = self.resume.take().unwrap();
+            if resume {
+                self.restore(state, time);
             }
-            side_positions
         }
-        // println!("width: {}, height: {}", layout.width, layout.height);
-        // for (pos, xy) in &layout.mapping {
-        //     println!(
-        //         "{:?} -> {:?}",
-        //         pos.iter().map(|c| c.0).collect::<Vec<_>>(),
-        //         xy,
-        //     );
-        // }
-        App {
-            puzzle,
-            layout,
-            cam_nd_to_4d: CameraNdTo4d::new(shape.len()),
-            cam_4d_to_3d: Camera4dTo3d::new(),
-            cam_3d_to_2d: Camera3dTo2d::new(),
-            side_positions: get_side_positions(shape),
-            turn_builder: TurnBuilder::new(shape),
-            clicked_pieces: HashSet::new(),
-            internal_color: Color32::DARK_GRAY,
-            internal_format: StickerFormat {
-                outline_color: Color32::BLACK,
-                outline_width: 0.05,
-                sticker_scale: 1.0,
-                sticker_opacity: 1.0,
-            },
-            hovered_format: StickerFormatBuilder {
-                outline_color: Some(Color32::WHITE),
-                outline_width: Some(0.1),
-                sticker_scale: None,
-                sticker_opacity: None,
-            },
-            clicked_format: StickerFormatBuilder {
-                outline_color: Some(Color32::LIGHT_GRAY),
-                outline_width: Some(0.1),
-                sticker_scale: None,
-                sticker_opacity: None,
-            },
-            gripped_format: StickerFormatBuilder {
-                outline_color: Some(Color32::GRAY),
-                outline_width: Some(0.05),
-                sticker_scale: None,
-                sticker_opacity: None,
-            },
-            default_filter_format: StickerFormat {
-                outline_color: Color32::BLACK,
-                outline_width: 0.05,
-                sticker_scale: 0.7,
-                sticker_opacity: 0.5,
-            },
-            default_no_filter_format: StickerFormat {
-                outline_color: Color32::BLACK,
-                outline_width: 0.05,
-                sticker_scale: 1.0,
-                sticker_opacity: 1.0,
-            },
-            filter_sequence: FilterSequence(Vec::new()),
-            filter_stage: None,
-            ui_dim: shape.len(),
-            ui_cuts: shape.iter().map(|c| c.0).collect(),
+    }
+
+    /// saves the settings if they changed, at most once a second unless `now`
+    fn save_settings(&mut self, time: f64, now: bool) {
+        if !now && time - self.settings_save_time < 1.0 {
+            return;
+        }
+        self.settings_save_time = time;
+        let settings = self.settings();
+        if settings == self.saved_settings {
+            return;
         }
+        let path = Self::settings_path();
+        let saved = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(&path, &settings));
+        if let Err(err) = saved {
+            println!("couldn't save settings: {err}");
+        }
+        // don't keep trying if it failed
+        self.saved_settings = settings;
     }
 
-    fn rebuild(&mut self) {
-        let shape: Vec<Cut> = self.ui_cuts[..self.ui_dim]
-            .iter()
-            .map(|&c| Cut(c))
-            .collect();
-        let ui_dim = self.ui_dim;
-        let ui_cuts = self.ui_cuts.clone();
-        *self = App::new(&shape);
-        self.ui_dim = ui_dim;
-        self.ui_cuts = ui_cuts;
+    fn ui_format_builder(ui: &mut egui::Ui, name: &str, format: &mut StickerFormatBuilder) {
+        ui.horizontal(|ui| {
+            ui.label(name);
+            let mut has_color = format.outline_color.is_some();
+            ui.checkbox(&mut has_color, "outline");
+            match (has_color, &mut format.outline_color) {
+                (true, Some(color)) => {
+                    egui::color_picker::color_edit_button_srgba(
+                        ui,
+                        color,
+                        egui::color_picker::Alpha::OnlyBlend,
+                    );
+                }
+                (true, color @ None) => *color = Some(Color32::GRAY),
+                (false, color) => *color = None,
+            }
+            for (value, text) in [
+                (&mut format.outline_width, "width"),
+                (&mut format.sticker_scale, "scale"),
+                (&mut format.sticker_opacity, "opacity"),
+            ] {
+                let mut has_value = value.is_some();
+                ui.checkbox(&mut has_value, text);
+                match (has_value, value) {
+                    (true, Some(x)) => {
+                        ui.add(egui::DragValue::new(x).range(0.0..=1.0).speed(0.01));
+                    }
+                    (true, value @ None) => *value = Some(0.1),
+                    (false, value) => *value = None,
+                }
+            }
+        });
+    }
+
+    fn ui_format(ui: &mut egui::Ui, name: &str, format: &mut StickerFormat) {
+        ui.horizontal(|ui| {
+            ui.label(name);
+            egui::color_picker::color_edit_button_srgba(
+                ui,
+                &mut format.outline_color,
+                egui::color_picker::Alpha::OnlyBlend,
+            );
+            for (value, text) in [
+                (&mut format.outline_width, "width"),
+                (&mut format.sticker_scale, "scale"),
+                (&mut format.sticker_opacity, "opacity"),
+            ] {
+                ui.label(text);
+                ui.add(egui::DragValue::new(value).range(0.0..=1.0).speed(0.01));
+            }
+        });
+    }
+
+    fn ui_settings(&mut self, ui: &mut egui::Ui) {
+        ui.label(format!("saved to {}", Self::settings_path().display()));
+        if ui.button("reset to defaults").clicked() {
+            let defaults = App::new(&self.puzzle.shape).settings();
+            self.apply_settings(&defaults);
+        }
+        ui.horizontal(|ui| {
+            ui.label("internal");
+            egui::color_picker::color_edit_button_srgba(
+                ui,
+                &mut self.internal_color,
+                egui::color_picker::Alpha::OnlyBlend,
+            );
+        });
+        Self::ui_format(ui, "internal", &mut self.internal_format);
+        Self::ui_format_builder(ui, "hovered", &mut self.hovered_format);
+        Self::ui_format_builder(ui, "clicked", &mut self.clicked_format);
+        Self::ui_format_builder(ui, "gripped", &mut self.gripped_format);
+        Self::ui_format_builder(ui, "grip axes", &mut self.grip_axis_format);
+        Self::ui_format(ui, "filtered", &mut self.default_filter_format);
+        Self::ui_format(ui, "unfiltered", &mut self.default_no_filter_format);
+
+        ui.label("keys");
+        let mut keybinds = self.keybinds.clone();
+        for (keys, text) in [
+            (&mut keybinds.pos_sides, "positive sides"),
+            (&mut keybinds.neg_sides, "negative sides"),
+            (&mut keybinds.axes, "axes"),
+        ] {
+            ui.horizontal(|ui| {
+                let mut text_keys = keys.iter().collect::<String>();
+                if ui.text_edit_singleline(&mut text_keys).changed() {
+                    *keys = text_keys.chars().collect();
+                }
+                ui.label(text);
+            });
+        }
+        ui.horizontal(|ui| {
+            let mut text_key = keybinds.puzzle.to_string();
+            if ui.text_edit_singleline(&mut text_key).changed()
+                && let Some(key) = text_key.chars().last()
+            {
+                keybinds.puzzle = key;
+            }
+            ui.label("whole puzzle");
+        });
+        ui.label("hold shift for axes past the end of a list");
+        if keybinds.is_valid() {
+            self.keybinds = keybinds;
+        }
+    }
+
+    fn ui_colors(&mut self, ui: &mut egui::Ui) {
+        let dim = self.puzzle.shape.len();
+        ui.horizontal(|ui| {
+            for scheme in ColorScheme::builtin() {
+                if ui.button(&scheme.name).clicked() {
+                    self.color_scheme = scheme;
+                }
+            }
+        });
+        let mut to_delete = None;
+        for (i, scheme) in self.color_schemes.0.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button(&scheme.name).clicked() {
+                    self.color_scheme = scheme.clone();
+                }
+                if ui.button("delete").clicked() {
+                    to_delete = Some(i);
+                }
+            });
+        }
+        if let Some(i) = to_delete {
+            self.color_schemes.0.remove(i);
+            if let Err(err) = self.color_schemes.save() {
+                println!("couldn't save color schemes: {err}");
+            }
+        }
+        ui.horizontal_wrapped(|ui| {
+            for side in (0..dim as i16).flat_map(|axis| [Side::new(axis), !Side::new(axis)]) {
+                let mut color = self.color_scheme.color(side);
+                ui.label(side.name().to_string());
+                if egui::color_picker::color_edit_button_srgba(
+                    ui,
+                    &mut color,
+                    egui::color_picker::Alpha::Opaque,
+                )
+                .changed()
+                {
+                    self.color_scheme.colors.insert(side, color);
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.ui_color_scheme_name);
+            if ui.button("save").clicked() && !self.ui_color_scheme_name.is_empty() {
+                let name = std::mem::take(&mut self.ui_color_scheme_name);
+                self.color_schemes.0.retain(|scheme| scheme.name != name);
+                self.color_scheme.name = name;
+                self.color_schemes.0.push(self.color_scheme.clone());
+                if let Err(err) = self.color_schemes.save() {
+                    println!("couldn't save color schemes: {err}");
+                }
+            }
+        });
+        ui.checkbox(&mut self.sticker_labels, "side letters on stickers");
+    }
+
+    fn ui_macros(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.ui_macro_name);
+            if let Some(recording) = &self.recording {
+                ui.label(format!("{} turns", recording.len()));
+                if ui.button("save").clicked() {
+                    let name = std::mem::take(&mut self.ui_macro_name);
+                    let turns = self.recording.take().unwrap();
+                    self.macros.0.retain(|m| m.name != name);
+                    self.macros.0.push(Macro::new(name, turns));
+                    if let Err(err) = self.macros.save() {
+                        println!("couldn't save macros: {err}");
+                    }
+                }
+                if ui.button("cancel").clicked() {
+                    self.recording = None;
+                }
+            } else if ui.button("record").clicked() {
+                self.recording = Some(Vec::new());
+            }
+        });
+        if !self.macros.0.is_empty() {
+            ui.horizontal(|ui| {
+                ui.label("mirror across");
+                self.ui_mirror_axis(ui);
+            });
+        }
+        let mut to_play = None;
+        let mut to_delete = None;
+        for (i, m) in self.macros.0.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button("play").clicked() {
+                    to_play = Some((i, false));
+                }
+                if ui.button("mirrored").clicked() {
+                    to_play = Some((i, true));
+                }
+                if ui.button("delete").clicked() {
+                    to_delete = Some(i);
+                }
+                ui.label(format!("{} ({})", m.name, m.grip.name()))
+                    .on_hover_text(m.turns.iter().join(" "));
+            });
+        }
+        if let Some((i, mirrored)) = to_play {
+            self.play_macro(i, mirrored);
+        }
+        if let Some(i) = to_delete {
+            self.macros.0.remove(i);
+            if let Err(err) = self.macros.save() {
+                println!("couldn't save macros: {err}");
+            }
+        }
     }
 
     // #[inline(never)]
@@ -1443,703 +6091,1980 @@ impl App {
     //     println!("image save in {:?}", start.elapsed());
     // }
 
-    fn ui_puzzle(&mut self, ui: &mut egui::Ui) {
-        // allocate the rect and handle camera motion
-        let screen_rect = {
-            let response = ui.allocate_response(ui.available_size(), egui::Sense::click_and_drag());
-            let egui::Vec2 { x: dx, y: dy } = response.drag_motion();
-            const DRAG_SENSITIVITY: f32 = 0.01;
-            let (shift, ctrl) = ui.ctx().input(|i| (i.modifiers.shift, i.modifiers.ctrl));
-            match (shift, ctrl) {
-                // 3d rotation
-                (false, false) => {
-                    self.cam_3d_to_2d.rot = na::Rotation3::from_euler_angles(
-                        dy * DRAG_SENSITIVITY,
-                        dx * DRAG_SENSITIVITY,
-                        0.0,
-                    ) * self.cam_3d_to_2d.rot;
+    fn ui_puzzle(&mut self, ui: &mut egui::Ui) {
+        // allocate the rect and handle camera motion
+        let screen_rect = {
+            let response = ui.allocate_response(ui.available_size(), egui::Sense::click_and_drag());
+            let egui::Vec2 { x: dx, y: dy } = response.drag_motion();
+            const DRAG_SENSITIVITY: f32 = 0.01;
+            let (shift, ctrl, time) = ui
+                .ctx()
+                .input(|i| (i.modifiers.shift, i.modifiers.ctrl, i.time));
+            if response.dragged() {
+                self.view_transition = None;
+            } else if let Some(transition) = &self.view_transition {
+                let t = ((time - transition.start) / ViewTransition::DURATION).min(1.0) as f32;
+                // smoothstep
+                let view = transition
+                    .from
+                    .lerp(&transition.to, t * t * (3.0 - 2.0 * t));
+                view.set(
+                    &mut self.cam_nd_to_4d,
+                    &mut self.cam_4d_to_3d,
+                    &mut self.cam_3d_to_2d,
+                );
+                if t >= 1.0 {
+                    self.view_transition = None;
+                }
+            }
+            if response.drag_stopped() && self.snap_view {
+                // snap the stage that was being dragged
+                let stages = match (shift, ctrl) {
+                    (false, false) => [true, false, false],
+                    (true, false) => [false, true, false],
+                    (_, true) => [false, false, true],
+                };
+                let view = self.view().snapped(stages);
+                self.animate_view(view, time);
+            }
+            match (shift, ctrl) {
+                // 3d rotation
+                (false, false) => {
+                    self.cam_3d_to_2d.rot = na::Rotation3::from_euler_angles(
+                        dy * DRAG_SENSITIVITY,
+                        dx * DRAG_SENSITIVITY,
+                        0.0,
+                    ) * self.cam_3d_to_2d.rot;
+                }
+                // 4d rotation
+                (true, false) => {
+                    // Rotate in the plane of (screen_axis, w) so the rotation is
+                    // view-relative (affected by the current 3d camera orientation).
+                    //
+                    // screen_x in 3d = R3^T * e1 = first row of cam_3d_to_2d.rot
+                    // screen_y in 3d = R3^T * (-e2) = negative second row (y is flipped)
+                    //
+                    // Givens rotation in plane (a, b):
+                    //   R = I + (c-1)*(a*aT + b*bT) + s*(b*aT - a*bT)
+                    let r3 = &self.cam_3d_to_2d.rot;
+                    let screen_x = na::Vector4::new(r3[(0, 0)], r3[(0, 1)], r3[(0, 2)], 0.0);
+                    let screen_y = na::Vector4::new(-r3[(1, 0)], -r3[(1, 1)], -r3[(1, 2)], 0.0);
+                    let w_axis = na::Vector4::new(0.0_f32, 0.0, 0.0, 1.0);
+
+                    let (s, c) = (dx * DRAG_SENSITIVITY).sin_cos();
+                    let r_xw = na::Matrix4::<f32>::identity()
+                        + (c - 1.0)
+                            * (screen_x * screen_x.transpose() + w_axis * w_axis.transpose())
+                        + s * (w_axis * screen_x.transpose() - screen_x * w_axis.transpose());
+
+                    let (s, c) = (dy * DRAG_SENSITIVITY).sin_cos();
+                    let r_yw = na::Matrix4::<f32>::identity()
+                        + (c - 1.0)
+                            * (screen_y * screen_y.transpose() + w_axis * w_axis.transpose())
+                        + s * (w_axis * screen_y.transpose() - screen_y * w_axis.transpose());
+
+                    self.cam_4d_to_3d.rot = r_xw * r_yw * self.cam_4d_to_3d.rot;
+                }
+                // nd rotation, of the visible axes against the hidden ones
+                (shift, true) => {
+                    // a direction in 3d after cam_4d_to_3d, as a direction in nd
+                    let to_nd = |v: na::Vector4<f32>| {
+                        let v = self.cam_4d_to_3d.rot.transpose() * v;
+                        self.cam_nd_to_4d.rot.transpose() * v
+                    };
+                    let r3 = &self.cam_3d_to_2d.rot;
+                    let screen_x = to_nd(na::Vector4::new(r3[(0, 0)], r3[(0, 1)], r3[(0, 2)], 0.0));
+                    let screen_y =
+                        to_nd(na::Vector4::new(-r3[(1, 0)], -r3[(1, 1)], -r3[(1, 2)], 0.0));
+                    let w_axis = to_nd(na::Vector4::new(0.0, 0.0, 0.0, 1.0));
+                    let hidden = self.cam_nd_to_4d.hidden_from(self.drag_hidden);
+                    // ctrl turns the screen axes towards the picked hidden axis,
+                    // ctrl+shift turns the w axis towards it and the next
+                    let planes = if shift {
+                        [(&w_axis, hidden.first(), dx), (&w_axis, hidden.get(1), dy)]
+                    } else {
+                        [
+                            (&screen_x, hidden.first(), dx),
+                            (&screen_y, hidden.first(), dy),
+                        ]
+                    };
+                    for (a, b, angle) in planes {
+                        if let Some(b) = b {
+                            self.cam_nd_to_4d.rotate(a, b, angle * DRAG_SENSITIVITY);
+                        }
+                    }
+                }
+            }
+            (
+                response.rect,
+                response.clicked(),
+                response.secondary_clicked(),
+                response.hover_pos(),
+            )
+        };
+        let (screen_rect, clicked, secondary_clicked, pointer) = screen_rect;
+        let center = screen_rect.center();
+        let scale = screen_rect.width().min(screen_rect.height()) * 0.20;
+        let painter = ui.painter();
+
+        let outline_color = Color32::from_gray(30);
+        let outline_width = scale * 0.008;
+        let fog_color = ui.visuals().extreme_bg_color;
+
+        /// the center of a cell and its quads, with corners in order around the edge
+        type Cell<V> = (V, Vec<[V; 4]>);
+
+        /// a 3-cube of the sticker's cell, spanned by `cell_axes`,
+        /// and its center. drawing every square of the (n-1)-cube is too slow.
+        fn sticker_geom_nd(
+            shape: &[Cut],
+            pos: &[Coord],
+            sticker_size: f32,
+            face_spacing: f32,
+            cell_axes: [usize; 3],
+        ) -> Cell<Box<[f32]>> {
+            let axis = pos
+                .iter()
+                .zip(shape.iter())
+                .position(|(coord, cut)| coord.0.abs() == cut.0)
+                .unwrap();
+            let mut center: Box<[f32]> = pos.iter().map(|c| c.0 as f32).collect();
+            center[axis] += face_spacing * (pos[axis].0.signum() as f32);
+            let quads = unit_cube_3d().map(|quad| {
+                quad.map(|cube| {
+                    let mut vert = center.clone();
+                    for (ax, offset) in cell_axes.into_iter().zip(cube) {
+                        vert[ax] += offset * sticker_size;
+                    }
+                    for (v, cut) in vert.iter_mut().zip(shape) {
+                        *v /= cut.0 as f32;
+                    }
+                    vert
+                })
+            });
+            for (v, cut) in center.iter_mut().zip(shape) {
+                *v /= cut.0 as f32;
+            }
+            (center, quads.to_vec())
+        }
+
+        fn unit_cube_3d() -> [[[f32; 3]; 4]; 6] {
+            [
+                // +x face
+                [
+                    [0.5, -0.5, -0.5],
+                    [0.5, 0.5, -0.5],
+                    [0.5, 0.5, 0.5],
+                    [0.5, -0.5, 0.5],
+                ],
+                // -x face
+                [
+                    [-0.5, -0.5, -0.5],
+                    [-0.5, 0.5, -0.5],
+                    [-0.5, 0.5, 0.5],
+                    [-0.5, -0.5, 0.5],
+                ],
+                // +y face
+                [
+                    [-0.5, 0.5, -0.5],
+                    [0.5, 0.5, -0.5],
+                    [0.5, 0.5, 0.5],
+                    [-0.5, 0.5, 0.5],
+                ],
+                // -y face
+                [
+                    [-0.5, -0.5, -0.5],
+                    [0.5, -0.5, -0.5],
+                    [0.5, -0.5, 0.5],
+                    [-0.5, -0.5, 0.5],
+                ],
+                // +z face
+                [
+                    [-0.5, -0.5, 0.5],
+                    [0.5, -0.5, 0.5],
+                    [0.5, 0.5, 0.5],
+                    [-0.5, 0.5, 0.5],
+                ],
+                // -z face
+                [
+                    [-0.5, -0.5, -0.5],
+                    [0.5, -0.5, -0.5],
+                    [0.5, 0.5, -0.5],
+                    [-0.5, 0.5, -0.5],
+                ],
+            ]
+        }
+
+        fn sticker_geom_4d(
+            shape: &[Cut; 4],
+            pos: &[Coord; 4],
+            sticker_size: f32,
+            face_spacing: f32,
+        ) -> [[[f32; 4]; 4]; 6] {
+            assert_eq!(pos.len(), 4);
+            let axis = pos
+                .iter()
+                .zip(shape.iter())
+                .position(|(coord, cut)| coord.0.abs() == cut.0)
+                .unwrap();
+            let float_pos: Vec<f32> = pos.iter().map(|c| c.0 as f32).collect();
+            let float_pos: [f32; 4] = float_pos.try_into().unwrap();
+            let cube = unit_cube_3d();
+            cube.map(|quad| {
+                quad.map(|[cube_x, cube_y, cube_z]| {
+                    let mut vert = float_pos;
+                    for ax in 0..4 {
+                        if ax == axis {
+                            vert[ax] += if pos[axis].0 > 0 {
+                                face_spacing
+                            } else {
+                                -face_spacing
+                            };
+                        } else if ax == (axis + 1) % 4 {
+                            vert[ax] += cube_x * sticker_size;
+                        } else if ax == (axis + 2) % 4 {
+                            vert[ax] += cube_y * sticker_size;
+                        } else {
+                            vert[ax] += cube_z * sticker_size;
+                        }
+                        vert[ax] /= shape[ax].0 as f32;
+                    }
+                    vert
+                })
+            })
+        }
+
+        fn unit_cube_2d() -> [[f32; 2]; 4] {
+            [[-0.5, -0.5], [0.5, -0.5], [0.5, 0.5], [-0.5, 0.5]]
+        }
+
+        fn sticker_geom_3d(
+            shape: &[Cut; 3],
+            pos: &[Coord; 3],
+            sticker_size: f32,
+            face_spacing: f32,
+        ) -> [[f32; 3]; 4] {
+            let axis = pos
+                .iter()
+                .zip(shape.iter())
+                .position(|(coord, cut)| coord.0.abs() == cut.0)
+                .unwrap();
+            let float_pos: Vec<f32> = pos.iter().map(|c| c.0 as f32).collect();
+            let float_pos: [f32; 3] = float_pos.try_into().unwrap();
+            let cube = unit_cube_2d();
+            cube.map(|[cube_x, cube_y]| {
+                let mut vert = float_pos;
+                for ax in 0..3 {
+                    if ax == axis {
+                        vert[ax] += if pos[axis].0 > 0 {
+                            face_spacing - 0.5
+                        } else {
+                            0.5 - face_spacing
+                        };
+                    } else if ax == (axis + 1) % 3 {
+                        vert[ax] += cube_x * sticker_size;
+                    } else {
+                        vert[ax] += cube_y * sticker_size;
+                    }
+                    vert[ax] /= shape[ax].0 as f32;
+                }
+                vert
+            })
+        }
+
+        // fn sticker_geom_3d(shape: &[Cut; 3], pos: &[Coord; 3]) -> [[f32; 3]; 4] {
+        //     let axis = pos
+        //         .iter()
+        //         .zip(shape.iter())
+        //         .position(|(coord, cut)| coord.0.abs() == cut.0)
+        //         .unwrap();
+        //     let float_pos: Vec<f32> = pos.iter().map(|c| c.0 as f32).collect();
+        //     let float_pos: [f32; 3] = float_pos.try_into().unwrap();
+        //     let mut verts = Vec::with_capacity(4);
+        //     for bits in 0..(1 << 3) {
+        //         if (bits >> axis) & 1 == 1 {
+        //             continue;
+        //         }
+        //         let mut vert = float_pos;
+        //         for ax in 0..3 {
+        //             let bit = (bits >> ax) & 1;
+        //             vert[ax] += if ax == axis {
+        //                 if pos[axis].0 > 0 { -0.5 } else { 0.5 }
+        //             } else if bit == 0 {
+        //                 -0.5
+        //             } else {
+        //                 0.5
+        //             };
+        //             vert[ax] /= shape[ax].0 as f32;
+        //         }
+        //         verts.push(vert);
+        //     }
+        //     verts.try_into().unwrap()
+        // }
+
+        /// the 4d depth of the sticker's cell, and its center and quads in 3d,
+        /// or None if it's behind the 4d camera.
+        #[allow(clippy::too_many_arguments)]
+        fn sticker_geom_to_3d(
+            cam_nd_to_4d: &CameraNdTo4d,
+            cam_4d_to_3d: &Camera4dTo3d,
+            shape: &[Cut],
+            pos: &[Coord],
+            sticker_size: f32,
+            face_spacing: f32,
+            explode: f32,
+            cell_axes: [usize; 3],
+        ) -> Option<(f32, Cell<[f32; 3]>)> {
+            // projects from 4d, keeping track of whether anything is behind the camera
+            // and of the depth of the last vertex, which is the center
+            let mut behind = false;
+            // the plane w = 1, where 3d puzzles are
+            let mut last_depth = 1.0;
+            let mut project_4d = |vert: &[f32; 4]| {
+                let (vert, depth) = cam_4d_to_3d.project(vert);
+                behind |= depth <= 0.0;
+                last_depth = depth;
+                vert
+            };
+            let ret = match pos.len() {
+                3 => {
+                    let axis = pos
+                        .iter()
+                        .zip(shape.iter())
+                        .position(|(coord, cut)| coord.0.abs() == cut.0)
+                        .unwrap();
+                    // the middle of the piece the sticker is on
+                    let mut center = pos.iter().map(|c| c.0 as f32).collect::<Vec<_>>();
+                    center[axis] -= pos[axis].0.signum() as f32;
+                    for (c, cut) in center.iter_mut().zip(shape) {
+                        *c /= cut.0 as f32;
+                    }
+                    (
+                        *center.as_array().unwrap(),
+                        vec![sticker_geom_3d(
+                            shape.try_into().unwrap(),
+                            pos.try_into().unwrap(),
+                            sticker_size,
+                            face_spacing,
+                        )],
+                    )
+                }
+                4 => {
+                    let pos: &[Coord; 4] = pos.try_into().unwrap();
+                    let axis = pos
+                        .iter()
+                        .zip(shape.iter())
+                        .position(|(coord, cut)| coord.0.abs() == cut.0)
+                        .unwrap();
+                    // pushes the whole cell out, after scaling to the puzzle
+                    let push = |mut vert: [f32; 4]| {
+                        vert[axis] += explode * pos[axis].0.signum() as f32;
+                        vert
+                    };
+                    let mut center = pos.map(|c| c.0 as f32);
+                    center[axis] += face_spacing * pos[axis].0.signum() as f32;
+                    for (c, cut) in center.iter_mut().zip(shape) {
+                        *c /= cut.0 as f32;
+                    }
+                    let quads =
+                        sticker_geom_4d(shape.try_into().unwrap(), pos, sticker_size, face_spacing)
+                            .map(|quad| quad.map(|vert| project_4d(&push(vert))));
+                    (project_4d(&push(center)), quads.to_vec())
+                }
+                5.. => {
+                    let axis = pos
+                        .iter()
+                        .zip(shape.iter())
+                        .position(|(coord, cut)| coord.0.abs() == cut.0)
+                        .unwrap();
+                    // pushes the whole cell out and projects it to 4d
+                    let push = |mut vert: Box<[f32]>| {
+                        vert[axis] += explode * pos[axis].0.signum() as f32;
+                        cam_nd_to_4d.project(&vert)
+                    };
+                    let (center, quads) =
+                        sticker_geom_nd(shape, pos, sticker_size, face_spacing, cell_axes);
+                    let quads = quads
+                        .into_iter()
+                        .map(|quad| quad.map(|vert| project_4d(&push(vert))))
+                        .collect();
+                    (project_4d(&push(center)), quads)
+                }
+                _ => todo!(),
+            };
+            // the center is always projected last
+            (!behind).then_some((last_depth, ret))
+        }
+
+        let culled_sides = self.culled_sides();
+
+        // for 5d and up, the 3 axes of each cell that show up biggest in 4d,
+        // indexed by the axis of the cell
+        let dim = self.puzzle.shape.len();
+        let cell_axes = (0..dim)
+            .map(|axis| {
+                let mut axes = (0..dim)
+                    .filter(|&ax| ax != axis)
+                    .sorted_by(|&a, &b| {
+                        let norm = |ax: usize| self.cam_nd_to_4d.rot.column(ax).norm();
+                        norm(b).total_cmp(&norm(a))
+                    })
+                    .chain([0, 1, 2])
+                    .take(3)
+                    .collect::<Vec<_>>();
+                axes.sort();
+                *axes.as_array().unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        // every visible quad, in the rotated 3d space, to be sorted by depth
+        let eye = self.cam_3d_to_2d.eye();
+        let to_screen = |vert: &na::Vector3<f32>| {
+            let ([x, y], z) = self.cam_3d_to_2d.project_rotated(vert);
+            let depth = match eye {
+                // further from the eye is deeper
+                Some(eye) => -(eye - vert).norm(),
+                None => z,
+            };
+            (
+                egui::Pos2::new(center.x + x * scale, center.y - y * scale),
+                depth,
+            )
+        };
+        // whether a point on screen is in a convex polygon, going either way around
+        let inside = |corners: &[egui::Pos2], point: egui::Pos2| {
+            let crosses = corners
+                .iter()
+                .circular_tuple_windows()
+                .map(|(a, b)| (*b - *a).x * (point - *a).y - (*b - *a).y * (point - *a).x)
+                .collect_vec();
+            crosses.iter().all(|&cross| cross >= 0.0) || crosses.iter().all(|&cross| cross <= 0.0)
+        };
+        let hint = self.current_hint();
+        let mut polygons = Vec::new();
+        for (sticker, color_side) in &self.puzzle.stickers {
+            let pos = &sticker.0.0;
+            let normal_axis = pos
+                .iter()
+                .zip(&self.puzzle.shape)
+                .position(|(coord, cut)| coord.0.abs() == cut.0)
+                .unwrap();
+            let side = Side::new(normal_axis as i16);
+            let side = if pos[normal_axis].0 > 0 { side } else { !side };
+            if culled_sides.contains(&side) {
+                continue;
+            }
+
+            let stroke = match self.highlight_format(&sticker.piece(&self.puzzle.shape), hint) {
+                Some(format) => {
+                    // so that an outline_width of 0.05 is the normal width
+                    const OUTLINE_SCALE: f32 = 0.16;
+                    egui::Stroke::new(
+                        format.outline_width.unwrap() * OUTLINE_SCALE * scale,
+                        format.outline_color.unwrap(),
+                    )
                 }
-                // 4d rotation
-                (true, false) => {
-                    // Rotate in the plane of (screen_axis, w) so the rotation is
-                    // view-relative (affected by the current 3d camera orientation).
-                    //
-                    // screen_x in 3d = R3^T * e1 = first row of cam_3d_to_2d.rot
-                    // screen_y in 3d = R3^T * (-e2) = negative second row (y is flipped)
-                    //
-                    // Givens rotation in plane (a, b):
-                    //   R = I + (c-1)*(a*aT + b*bT) + s*(b*aT - a*bT)
-                    let r3 = &self.cam_3d_to_2d.rot;
-                    let screen_x = na::Vector4::new(r3[(0, 0)], r3[(0, 1)], r3[(0, 2)], 0.0);
-                    let screen_y = na::Vector4::new(-r3[(1, 0)], -r3[(1, 1)], -r3[(1, 2)], 0.0);
-                    let w_axis = na::Vector4::new(0.0_f32, 0.0, 0.0, 1.0);
+                None => egui::Stroke::new(outline_width, outline_color),
+            };
 
-                    let (s, c) = (dx * DRAG_SENSITIVITY).sin_cos();
-                    let r_xw = na::Matrix4::<f32>::identity()
-                        + (c - 1.0)
-                            * (screen_x * screen_x.transpose() + w_axis * w_axis.transpose())
-                        + s * (w_axis * screen_x.transpose() - screen_x * w_axis.transpose());
+            let Some((depth_4d, (cell_center, sticker_quads))) = sticker_geom_to_3d(
+                &self.cam_nd_to_4d,
+                &self.cam_4d_to_3d,
+                &self.puzzle.shape,
+                pos,
+                self.sticker_size,
+                self.face_spacing,
+                self.explode,
+                cell_axes[normal_axis],
+            ) else {
+                continue;
+            };
+            let cell_center = self.cam_3d_to_2d.rotate(&cell_center);
+            // fade into the background with 4d depth, which goes from 1 to 3 across the puzzle
+            let fog = self.fog * ((depth_4d - 1.0) / 2.0).clamp(0.0, 1.0);
+            let color = self
+                .color_scheme
+                .color(*color_side)
+                .lerp_to_gamma(fog_color, fog);
+
+            for quad in sticker_quads {
+                // shrink towards the middle, to leave gaps
+                let quad = {
+                    let middle = quad.map(na::Vector3::from).iter().sum::<na::Vector3<f32>>() / 4.0;
+                    quad.map(|vert| {
+                        let vert = middle + (na::Vector3::from(vert) - middle) * (1.0 - self.gap);
+                        [vert.x, vert.y, vert.z]
+                    })
+                };
 
-                    let (s, c) = (dy * DRAG_SENSITIVITY).sin_cos();
-                    let r_yw = na::Matrix4::<f32>::identity()
-                        + (c - 1.0)
-                            * (screen_y * screen_y.transpose() + w_axis * w_axis.transpose())
-                        + s * (w_axis * screen_y.transpose() - screen_y * w_axis.transpose());
+                // back face culling, in the rotated 3d space
+                let rotated = quad.map(|vert| self.cam_3d_to_2d.rotate(&vert));
+                let quad_center = rotated.iter().sum::<na::Vector3<f32>>() / 4.0;
+                let mut normal = (rotated[2] - rotated[0]).cross(&(rotated[3] - rotated[1]));
+                if normal.dot(&(quad_center - cell_center)) < 0.0 {
+                    normal = -normal;
+                }
+                let to_eye = eye.map_or(na::Vector3::z(), |eye| eye - quad_center);
+                if normal.dot(&to_eye) <= 0.0 {
+                    continue;
+                }
+                let color = if self.shading {
+                    Self::shade(color, &normal)
+                } else {
+                    color
+                };
 
-                    self.cam_4d_to_3d.rot = r_xw * r_yw * self.cam_4d_to_3d.rot;
+                // the letter is sized to the whole quad, and goes in the middle of it
+                let label = self.sticker_labels.then(|| {
+                    let corners = rotated.map(|vert| to_screen(&vert).0);
+                    let area = corners
+                        .iter()
+                        .circular_tuple_windows()
+                        .map(|(a, b)| a.x * b.y - b.x * a.y)
+                        .sum::<f32>()
+                        .abs()
+                        / 2.0;
+                    let middle = corners
+                        .iter()
+                        .fold(egui::Pos2::ZERO, |acc, p| acc + p.to_vec2() / 4.0);
+                    (color_side.name(), middle, area.sqrt() * 0.5)
+                });
+                polygons.push((
+                    Polygon::new(rotated.to_vec()),
+                    (label, color, stroke, sticker),
+                ));
+            }
+        }
+
+        // optionally split quads that cross, so they sort right,
+        // unless there are so many that it would take too long
+        if self.split_quads && polygons.len() <= Self::MAX_SPLIT_QUADS {
+            polygons = Polygon::split_crossing(polygons);
+        }
+        let mut quads = polygons
+            .into_iter()
+            .map(|(polygon, (label, color, stroke, sticker))| {
+                let (corners, depths): (Vec<_>, Vec<_>) =
+                    polygon.verts.iter().map(to_screen).unzip();
+                let depth = depths.iter().sum::<f32>() / depths.len() as f32;
+                // only the part the letter is in draws it
+                let label = label.filter(|(_, middle, _)| inside(&corners, *middle));
+                (corners, polygon.edges, label, depth, color, stroke, sticker)
+            })
+            .collect_vec();
+
+        // painter's algorithm, back to front
+        quads.sort_unstable_by(|a, b| a.3.total_cmp(&b.3));
+        // the frontmost sticker under the pointer, when it's clicked to paint
+        let painted = pointer
+            .filter(|_| self.painting && (clicked || secondary_clicked))
+            .and_then(|pointer| {
+                quads
+                    .iter()
+                    .rev()
+                    .find(|quad| inside(&quad.0, pointer))
+                    .map(|quad| quad.6.clone())
+            });
+        for (corners, edges, label, _depth, color, stroke, _sticker) in quads {
+            // the outline goes on top of the fill so that both get antialiased
+            painter.add(egui::Shape::convex_polygon(
+                corners.clone(),
+                color,
+                egui::Stroke::NONE,
+            ));
+            if edges.iter().all(|&edge| edge) {
+                painter.add(egui::Shape::closed_line(corners, stroke));
+            } else {
+                let n = corners.len();
+                for (k, _) in edges.iter().enumerate().filter(|(_, edge)| **edge) {
+                    painter.line_segment([corners[k], corners[(k + 1) % n]], stroke);
                 }
-                (false, true) => {}
-                (true, true) => {}
             }
-            response.rect
-        };
-        let center = screen_rect.center();
-        let scale = screen_rect.width().min(screen_rect.height()) * 0.20;
-        let painter = ui.painter();
+            if let Some((name, middle, size)) = label {
+                let text_color = if color.intensity() > 0.5 {
+                    Color32::BLACK
+                } else {
+                    Color32::WHITE
+                };
+                painter.text(
+                    middle,
+                    egui::Align2::CENTER_CENTER,
+                    name,
+                    egui::FontId::proportional(size),
+                    text_color,
+                );
+            }
+        }
 
-        let outline_color = Color32::from_gray(30);
-        let outline_width = scale * 0.008;
+        // key hints on the middle of each side, named by where the sides are on screen
+        let font = egui::TextStyle::Monospace.resolve(ui.style());
+        let render_axis_keys = match self.turn_builder {
+            TurnBuilder::Side { side, .. } => side.is_some(),
+            TurnBuilder::Puzzle { .. } => true,
+        };
+        for (side, piece) in &self.side_positions {
+            let screen_side = self.orientation.side(*side);
+            if (render_axis_keys && !screen_side.is_positive()) || culled_sides.contains(side) {
+                continue;
+            }
+            let axis = side.axis().into_usize();
+            let mut pos = piece.0.0.clone();
+            pos[axis] = if side.is_positive() {
+                Coord(self.puzzle.shape[axis].0)
+            } else {
+                Coord(-self.puzzle.shape[axis].0)
+            };
+            let Some((_, (cell_center, sticker_quads))) = sticker_geom_to_3d(
+                &self.cam_nd_to_4d,
+                &self.cam_4d_to_3d,
+                &self.puzzle.shape,
+                &pos,
+                self.sticker_size,
+                self.face_spacing,
+                self.explode,
+                cell_axes[axis],
+            ) else {
+                continue;
+            };
+            let middle = sticker_quads
+                .iter()
+                .flatten()
+                .map(|&vert| na::Vector3::from(vert))
+                .sum::<na::Vector3<f32>>()
+                / (sticker_quads.len() * 4) as f32;
+            // dim the keys of sides facing away in 3d
+            let normal = self
+                .cam_3d_to_2d
+                .rotate(&(middle - na::Vector3::from(cell_center)).into());
+            let to_eye = eye.map_or(na::Vector3::z(), |eye| {
+                eye - self.cam_3d_to_2d.rotate(&middle.into())
+            });
+            let color = if self.puzzle.shape.len() > 3 || normal.dot(&to_eye) > 0.0 {
+                Color32::LIGHT_GRAY
+            } else {
+                Color32::GRAY
+            };
+            let ([x, y], _) = self.cam_3d_to_2d.project(&middle.into());
+            painter.text(
+                egui::Pos2::new(center.x + x * scale, center.y - y * scale),
+                egui::Align2::CENTER_CENTER,
+                if render_axis_keys {
+                    self.keybinds
+                        .axis_key(screen_side.into_axis())
+                        .unwrap_or_default()
+                } else {
+                    self.keybinds.side_key(screen_side).unwrap_or_default()
+                },
+                font.clone(),
+                color,
+            );
+        }
 
-        fn sticker_geom_nd(pos: &[Coord]) {}
+        painter.text(
+            screen_rect.left_bottom() + egui::Vec2::new(10.0, -10.0),
+            egui::Align2::LEFT_BOTTOM,
+            self.turn_builder.status(),
+            font,
+            Color32::LIGHT_GRAY,
+        );
 
-        fn sticker_geom_5d(shape: &[Cut; 5], pos: &[Coord; 5]) -> [[[[f32; 5]; 4]; 6]; 8] {
-            todo!()
+        if let Some(sticker) = painted {
+            self.paint(&sticker, secondary_clicked);
         }
+    }
+}
+impl eframe::App for App {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.autosave(self.time, true);
+        // changes in the last second haven't been saved yet
+        self.save_settings(self.time, true);
+    }
 
-        fn unit_cube_3d() -> [[[f32; 3]; 4]; 6] {
-            [
-                // +x face
-                [
-                    [0.5, -0.5, -0.5],
-                    [0.5, 0.5, -0.5],
-                    [0.5, 0.5, 0.5],
-                    [0.5, -0.5, 0.5],
-                ],
-                // -x face
-                [
-                    [-0.5, -0.5, -0.5],
-                    [-0.5, 0.5, -0.5],
-                    [-0.5, 0.5, 0.5],
-                    [-0.5, -0.5, 0.5],
-                ],
-                // +y face
-                [
-                    [-0.5, 0.5, -0.5],
-                    [0.5, 0.5, -0.5],
-                    [0.5, 0.5, 0.5],
-                    [-0.5, 0.5, 0.5],
-                ],
-                // -y face
-                [
-                    [-0.5, -0.5, -0.5],
-                    [0.5, -0.5, -0.5],
-                    [0.5, -0.5, 0.5],
-                    [-0.5, -0.5, 0.5],
-                ],
-                // +z face
-                [
-                    [-0.5, -0.5, 0.5],
-                    [0.5, -0.5, 0.5],
-                    [0.5, 0.5, 0.5],
-                    [-0.5, 0.5, 0.5],
-                ],
-                // -z face
-                [
-                    [-0.5, -0.5, -0.5],
-                    [0.5, -0.5, -0.5],
-                    [0.5, 0.5, -0.5],
-                    [-0.5, 0.5, -0.5],
-                ],
-            ]
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // save before crashing, so the solve can be resumed.
+        // panics outside of drawing a frame aren't caught, but there's not much there.
+        let frame = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| self.frame(ctx)));
+        if let Err(panic) = frame {
+            self.autosave(self.time, true);
+            std::panic::resume_unwind(panic);
         }
+    }
+}
+impl App {
+    fn frame(&mut self, ctx: &egui::Context) {
+        ctx.request_repaint();
+        let time = ctx.input(|i| i.time);
+        self.time = time;
+        self.save_settings(time, false);
+        self.update_timer(time);
+        self.autosave(time, false);
+        self.ui_resume(ctx, time);
+        self.ui_toasts(ctx, time);
+        egui::CentralPanel::default()
+            .frame(egui::Frame::NONE)
+            .show(ctx, |ui| {
+                let wants_keyboard_input = ctx.wants_keyboard_input();
+                ctx.input(|i| {
+                    // handle input
+                    if wants_keyboard_input {
+                        return;
+                    }
+                    for event in i.events.iter() {
+                        if let egui::Event::Key {
+                            key,
+                            physical_key: _,
+                            pressed,
+                            repeat,
+                            modifiers,
+                        } = event
+                            && *pressed
+                            && !repeat
+                            && let Some(turn) =
+                                self.turn_builder
+                                    .update(*key, modifiers.shift, &self.keybinds)
+                        {
+                            match self.turn_on_screen(&turn, i.time) {
+                                Ok(()) => println!("solved: {}", self.puzzle.is_solved()),
+                                Err(err) => self.toast(format!("can't do {turn}: {err}")),
+                            }
+                        }
+                    }
+                });
 
-        fn sticker_geom_4d(shape: &[Cut; 4], pos: &[Coord; 4]) -> [[[f32; 4]; 4]; 6] {
-            assert_eq!(pos.len(), 4);
-            let axis = pos
-                .iter()
-                .zip(shape.iter())
-                .position(|(coord, cut)| coord.0.abs() == cut.0)
-                .unwrap();
-            let float_pos: Vec<f32> = pos.iter().map(|c| c.0 as f32).collect();
-            let float_pos: [f32; 4] = float_pos.try_into().unwrap();
-            let cube = unit_cube_3d();
-            cube.map(|quad| {
-                quad.map(|[cube_x, cube_y, cube_z]| {
-                    let mut vert = float_pos;
-                    for ax in 0..4 {
-                        if ax == axis {
-                            // const CELL_SPACING: f32 = -0.5;
-                            // const CELL_SPACING: f32 = 1.0;
-                            const CELL_SPACING: f32 = 2.0;
-                            vert[ax] += if pos[axis].0 > 0 {
-                                CELL_SPACING
-                            } else {
-                                -CELL_SPACING
-                            };
-                        } else if ax == (axis + 1) % 4 {
-                            vert[ax] += cube_x;
-                        } else if ax == (axis + 2) % 4 {
-                            vert[ax] += cube_y;
+                // draw puzzle with Camera
+                self.ui_puzzle(ui);
+
+                // draw puzzle with Layout2d
+                #[cfg(false)]
+                {
+                    // we want 1 sticker of margin on each side
+                    let rect = ctx.content_rect();
+                    let scale = f32::min(
+                        rect.width() / (self.layout.width + 2) as f32,
+                        rect.height() / (self.layout.height + 2) as f32,
+                    );
+
+                    let screen_of_pos = |pos: &Position| -> egui::Pos2 {
+                        let (x, y) = self.layout.mapping[pos];
+                        egui::Pos2::new(1.5 + x as f32, 1.5 + (self.layout.height - 1 - y) as f32)
+                            * scale
+                    };
+                    let pos_of_screen = |screen: egui::Pos2| -> Option<Position> {
+                        let x = (screen.x / scale - 1.5).round() as i32;
+                        let y =
+                            self.layout.height as i32 - 1 - (screen.y / scale - 1.5).round() as i32;
+                        if !(0..self.layout.width as _).contains(&x)
+                            || !(0..self.layout.height as _).contains(&y)
+                        {
+                            return None;
+                        }
+                        self.layout.inverse.get(&(x as usize, y as usize)).cloned()
+                    };
+
+                    let hovered_pos: Option<Position> =
+                        ui.input(|i| i.pointer.hover_pos()).and_then(pos_of_screen);
+                    let hovered_piece = hovered_pos.and_then(|pos| {
+                        Piece::try_from(&self.puzzle.shape, pos.clone()).or_else(|| {
+                            Sticker::try_from(&self.puzzle.shape, pos.clone())
+                                .map(|sticker| sticker.piece(&self.puzzle.shape))
+                        })
+                    });
+
+                    // if we clicked, added the hovered piece to the clicked pieces
+                    if let Some(hovered_piece) = &hovered_piece
+                        && ui.input(|i| i.pointer.button_pressed(egui::PointerButton::Primary))
+                    {
+                        if self.clicked_pieces.contains(hovered_piece) {
+                            self.clicked_pieces.remove(hovered_piece);
+                        } else {
+                            self.clicked_pieces.insert(hovered_piece.clone());
+                        }
+                    }
+
+                    let hint = self.current_hint();
+                    let format_sticker = |sticker: &Sticker| -> StickerFormat {
+                        let mut ret = StickerFormatBuilder::NONE;
+                        if let Some(hovered_piece) = hovered_piece.clone()
+                            && sticker.piece(&self.puzzle.shape) == hovered_piece
+                        {
+                            ret.update(&self.hovered_format);
+                        }
+                        for clicked_piece in &self.clicked_pieces {
+                            if sticker.piece(&self.puzzle.shape) == *clicked_piece {
+                                ret.update(&self.clicked_format);
+                            }
+                        }
+                        if let Some(format) =
+                            self.highlight_format(&sticker.piece(&self.puzzle.shape), hint)
+                        {
+                            ret.update(format);
+                        }
+                        if let Some(filter_stage) = self.filter_stage {
+                            for filter in &self.filter_sequence.0[filter_stage].0 {
+                                if filter.contains(
+                                    &self.puzzle.shape,
+                                    &sticker.piece(&self.puzzle.shape),
+                                ) {
+                                    ret.update(&filter.format);
+                                }
+                            }
+                            ret.build_or(&self.default_filter_format)
                         } else {
-                            vert[ax] += cube_z;
+                            ret.build_or(&self.default_no_filter_format)
                         }
-                        vert[ax] /= shape[ax].0 as f32;
+                    };
+
+                    let painter = ui.painter();
+                    // TODO: pixel alignment
+                    let draw_position = |pos: &Position, color: Color32, format: &StickerFormat| {
+                        let rect = egui::Rect::from_center_size(
+                            screen_of_pos(pos),
+                            egui::Vec2::new(1.0, 1.0) * scale * format.sticker_scale,
+                        );
+
+                        painter.rect(
+                            rect,
+                            // TODO: custom corner radius
+                            0.2 * scale * format.sticker_scale,
+                            // TODO: is unmultiplied correct
+                            Color32::from_rgba_unmultiplied(
+                                color.r(),
+                                color.g(),
+                                color.b(),
+                                (format.sticker_opacity * 255.0) as u8,
+                            ),
+                            egui::Stroke::new(format.outline_width * scale, format.outline_color),
+                            egui::StrokeKind::Inside,
+                        );
+                    };
+
+                    for pos in Position::all(&self.puzzle.shape) {
+                        draw_position(&pos, self.internal_color, &self.internal_format);
+                    }
+                    for (pos, side) in &self.puzzle.stickers {
+                        draw_position(&pos.0, self.color_scheme.color(*side), &format_sticker(pos));
                     }
-                    vert
-                })
-            })
-        }
 
-        fn unit_cube_2d() -> [[f32; 2]; 4] {
-            [[-0.5, -0.5], [0.5, -0.5], [0.5, 0.5], [-0.5, 0.5]]
-        }
+                    // TODO: fancy text sizing
+                    let render_axis_keys = match self.turn_builder {
+                        TurnBuilder::Side { side, .. } => side.is_some(),
+                        TurnBuilder::Puzzle { .. } => true,
+                    };
+                    for (side, pos) in &self.side_positions {
+                        if render_axis_keys && !side.is_positive() {
+                            continue;
+                        }
+                        painter.text(
+                            screen_of_pos(&pos.0),
+                            egui::Align2::CENTER_CENTER,
+                            if render_axis_keys {
+                                self.keybinds.axis_key(side.into_axis()).unwrap_or_default()
+                            } else {
+                                self.keybinds.side_key(*side).unwrap_or_default()
+                            },
+                            egui::TextStyle::Monospace.resolve(&ctx.style()),
+                            Color32::LIGHT_GRAY,
+                        );
+                    }
+                }
 
-        fn sticker_geom_3d(shape: &[Cut; 3], pos: &[Coord; 3]) -> [[f32; 3]; 4] {
-            let axis = pos
-                .iter()
-                .zip(shape.iter())
-                .position(|(coord, cut)| coord.0.abs() == cut.0)
-                .unwrap();
-            let float_pos: Vec<f32> = pos.iter().map(|c| c.0 as f32).collect();
-            let float_pos: [f32; 3] = float_pos.try_into().unwrap();
-            let cube = unit_cube_2d();
-            cube.map(|[cube_x, cube_y]| {
-                let mut vert = float_pos;
-                for ax in 0..3 {
-                    if ax == axis {
-                        vert[ax] += if pos[axis].0 > 0 { -0.5 } else { 0.5 };
-                    } else if ax == (axis + 1) % 3 {
-                        vert[ax] += cube_x;
-                    } else {
-                        vert[ax] += cube_y;
+                // painter.text(
+                //     egui::Pos2::new(10.0, ui.available_height() - 10.0),
+                //     egui::Align2::LEFT_BOTTOM,
+                //     format!("{:?}", self.turn_builder),
+                //     egui::TextStyle::Monospace.resolve(&ctx.style()),
+                //     Color32::LIGHT_GRAY,
+                // );
+
+                // debug seeing if stuff is aligned
+                #[cfg(false)]
+                {
+                    painter.line_segment(
+                        [egui::Pos2::ZERO, egui::Pos2::new(1000.0, 1000.0)],
+                        egui::Stroke::new(1.0, Color32::WHITE),
+                    );
+                    for i in 0..=self.layout.width.max(self.layout.height) + 2 {
+                        let c = i as f32 * scale;
+                        painter.line_segment(
+                            [egui::Pos2::new(c, 0.0), egui::Pos2::new(c, 1000.0)],
+                            egui::Stroke::new(1.0, Color32::WHITE),
+                        );
+                        painter.line_segment(
+                            [egui::Pos2::new(0.0, c), egui::Pos2::new(1000.0, c)],
+                            egui::Stroke::new(1.0, Color32::WHITE),
+                        );
                     }
-                    vert[ax] /= shape[ax].0 as f32;
                 }
-                vert
-            })
-        }
 
-        // fn sticker_geom_3d(shape: &[Cut; 3], pos: &[Coord; 3]) -> [[f32; 3]; 4] {
-        //     let axis = pos
-        //         .iter()
-        //         .zip(shape.iter())
-        //         .position(|(coord, cut)| coord.0.abs() == cut.0)
-        //         .unwrap();
-        //     let float_pos: Vec<f32> = pos.iter().map(|c| c.0 as f32).collect();
-        //     let float_pos: [f32; 3] = float_pos.try_into().unwrap();
-        //     let mut verts = Vec::with_capacity(4);
-        //     for bits in 0..(1 << 3) {
-        //         if (bits >> axis) & 1 == 1 {
-        //             continue;
-        //         }
-        //         let mut vert = float_pos;
-        //         for ax in 0..3 {
-        //             let bit = (bits >> ax) & 1;
-        //             vert[ax] += if ax == axis {
-        //                 if pos[axis].0 > 0 { -0.5 } else { 0.5 }
-        //             } else if bit == 0 {
-        //                 -0.5
-        //             } else {
-        //                 0.5
-        //             };
-        //             vert[ax] /= shape[ax].0 as f32;
-        //         }
-        //         verts.push(vert);
-        //     }
-        //     verts.try_into().unwrap()
-        // }
+                // `Area` to draw on top of the puzzle
+                egui::Area::new("ui".into())
+                    .constrain_to(ctx.content_rect())
+                    .anchor(egui::Align2::LEFT_TOP, egui::Vec2::ZERO)
+                    // .fixed_pos(egui::Pos2::new(0.0, 0.0))
+                    .show(ui.ctx(), |ui| {
+                        // draw ui
+                        ui.horizontal(|ui| {
+                            if ui.button("scramble").clicked() {
+                                if let Err(err) = self.check_resumed() {
+                                    self.toast(format!("can't scramble: {err}"));
+                                } else {
+                                    self.scramble = self.puzzle.scramble(&mut rand::rng());
+                                    self.history.clear();
+                                    self.simplified = SimplifiedTurns::default();
+                                    self.timer = Timer::new();
+                                }
+                            }
+                            ui.label(format!("{} moves", self.simplified.move_count()))
+                                .on_hover_ui(|ui| {
+                                    ui.label(self.simplified.text(&self.puzzle.shape));
+                                });
+                            ui.label(self.timer.format(time));
+                            ui.menu_button("mirror", |ui| {
+                                for axis in (0..self.puzzle.shape.len()).map(Axis::from_usize) {
+                                    if ui.button(format!("across {}", axis.name())).clicked()
+                                        && let Err(err) = self.mirror(axis)
+                                    {
+                                        self.toast(format!("can't mirror: {err}"));
+                                    }
+                                }
+                            })
+                            .response
+                            .on_hover_text("reflect the whole puzzle");
+                            ui.collapsing("shape", |ui| {
+                                if ui.button("build").clicked() {
+                                    let shape = self.ui_cuts[..self.ui_dim].iter().map(|&c| Cut(c));
+                                    if let Err(err) = self.check_resumed() {
+                                        self.toast(format!("can't build: {err}"));
+                                    } else if Cut::is_buildable(&shape.collect_vec()) {
+                                        self.rebuild();
+                                    } else {
+                                        self.toast("can't build a puzzle that big".into());
+                                    }
+                                }
+                                // bigger puzzles can be typed in, but they get slow
+                                ui.add(
+                                    egui::Slider::new(&mut self.ui_dim, 2..=7)
+                                        .clamping(egui::SliderClamping::Never)
+                                        .text("dim"),
+                                );
+                                self.ui_dim = self.ui_dim.clamp(1, Side::MAX_AXES);
+                                // grow/shrink ui_cuts to match ui_dim, defaulting new axes to 3
+                                self.ui_cuts.resize(self.ui_dim, 3);
+                                for i in 0..self.ui_dim {
+                                    ui.add(
+                                        egui::Slider::new(&mut self.ui_cuts[i], 1..=7)
+                                            .clamping(egui::SliderClamping::Never)
+                                            .text(format!("axis {}", i)),
+                                    );
+                                    self.ui_cuts[i] = self.ui_cuts[i].max(1);
+                                }
+                            });
+                            ui.collapsing("view", |ui| {
+                                self.ui_view(ui);
+                            });
+                            ui.collapsing("colors", |ui| {
+                                self.ui_colors(ui);
+                            });
+                            ui.collapsing("settings", |ui| {
+                                self.ui_settings(ui);
+                            });
+                            ui.collapsing("algorithm", |ui| {
+                                self.ui_algorithm(ui);
+                            });
+                            ui.collapsing("macros", |ui| {
+                                self.ui_macros(ui);
+                            });
+                            ui.collapsing("state", |ui| {
+                                self.ui_state(ui);
+                            });
+                            ui.collapsing("paint", |ui| {
+                                self.ui_paint(ui);
+                            });
+                            ui.collapsing("solver", |ui| {
+                                self.ui_solver(ui);
+                            });
+                            ui.collapsing("hint", |ui| {
+                                self.ui_hint(ui);
+                            });
+                        });
+                    });
+            });
+    }
+}
 
-        /// `ret` is a `Vec` of quads with vertices in 3d.
-        fn sticker_geom_to_3d(
-            cam_nd_to_4d: &CameraNdTo4d,
-            cam_4d_to_3d: &Camera4dTo3d,
-            shape: &[Cut],
-            pos: &[Coord],
-        ) -> Vec<[[f32; 3]; 4]> {
-            match pos.len() {
-                3 => vec![sticker_geom_3d(
-                    shape.try_into().unwrap(),
-                    pos.try_into().unwrap(),
-                )],
-                4 => sticker_geom_4d(shape.try_into().unwrap(), pos.try_into().unwrap())
-                    .map(|quad| quad.map(|vert| cam_4d_to_3d.project(&vert).0))
-                    .to_vec(),
-                _ => todo!(),
-            }
-        }
+fn main() -> eframe::Result {
+    // unsafe { std::env::set_var("RUST_BACKTRACE", "1") };
+    // env_logger::init();
 
-        // find the side that gets 4d_to_3d projected closest to the camera and cull it
-        let side_to_cull: Option<Side> = {
-            let dim = self.puzzle.shape.len();
-            if dim <= 3 {
-                None
-            } else {
-                Some(
-                    (-(dim as i16)..dim as i16)
-                        .map(Side)
-                        .map(|side| {
-                            let sign = if side.is_positive() { 1 } else { -1 };
-                            let axis = side.axis();
-                            let mut pos = vec![0; dim];
-                            pos[axis.into_usize()] = sign;
-                            let pos: Box<[f32]> = pos.iter().map(|c| *c as f32).collect();
-
-                            // project nd to 4d
-                            let pos = if pos.len() > 4 {
-                                self.cam_nd_to_4d.project(&pos)
-                            } else {
-                                *pos.as_array::<4>().unwrap()
-                            };
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.get(1).map(String::as_str) == Some("solve") {
+        solve_cli(&args[2..]);
+        return Ok(());
+    }
+
+    // let mut app = App::new(&[3, 3, 4, 5, 6, 7, 8].map(Cut));
+    // app.puzzle.scramble(&mut rand::rng());
+    // // app.render_png("render.png");
+    // std::hint::black_box(app);
+    // panic!();
 
-                            // project from 4d to 3d
-                            let (pos, depth) = self.cam_4d_to_3d.project(&pos);
+    // let app = App::new(&[2, 3, 4].map(Cut));
+    let app = App::load();
+    let native_options = eframe::NativeOptions::default();
+    eframe::run_native(
+        "rectangle",
+        native_options,
+        Box::new(|_cc| Ok(Box::new(app))),
+    )
+}
 
-                            (side, depth)
-                        })
-                        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
-                        .unwrap()
-                        .0,
-                )
+/// `rectangle solve <shape> [turns]`, eg `rectangle solve 2x2x3 "R[UF] U[FR]"`.
+/// scrambles randomly if there are no turns.
+#[inline(never)]
+fn solve_cli(args: &[String]) {
+    const USAGE: &str = "usage: rectangle solve <shape like 2x2x3> [turns]";
+    let Some(shape) = args.first().and_then(|shape| Cut::parse_shape(shape)) else {
+        println!("{USAGE}");
+        return;
+    };
+    let mut puzzle = Puzzle::new(&shape);
+    match args.get(1) {
+        Some(turns) => {
+            let turns = match Turn::parse_seq(turns) {
+                Ok(turns) => turns,
+                Err(err) => {
+                    println!("{err}");
+                    return;
+                }
+            };
+            for turn in &turns {
+                if let Err(err) = puzzle.turn(turn) {
+                    println!("can't do {turn}: {err}");
+                    return;
+                }
             }
-        };
+        }
+        None => {
+            let scramble = puzzle.scramble(&mut rand::rng());
+            println!("scramble: {}", Turn::format_seq(&scramble));
+        }
+    }
+    match Solver::new(&shape).and_then(|solver| solver.solve(&puzzle)) {
+        Ok(turns) => println!("solution: {}", Turn::format_seq(&turns)),
+        Err(err) => println!("{err}"),
+    }
+}
 
-        // stickers sorted by depth
-        let stickers = {
-            let mut sticker_depths = Vec::new();
-            for (sticker, color_side) in &self.puzzle.stickers {
-                let pos = &sticker.0.0;
-                // cull if the sticker is on the side_to_cull
-                if let Some(side_to_cull) = side_to_cull {
-                    let axis_to_cull = side_to_cull.axis();
-                    if pos[axis_to_cull.into_usize()]
-                        == if side_to_cull.is_positive() {
-                            self.puzzle.shape[axis_to_cull.into_usize()]
-                                .coords()
-                                .last()
-                                .unwrap()
-                        } else {
-                            self.puzzle.shape[axis_to_cull.into_usize()]
-                                .coords()
-                                .next()
-                                .unwrap()
-                        }
-                    {
-                        continue;
-                    }
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(s: &str) -> Turn {
+        let turns = Turn::parse_seq(s).unwrap();
+        assert_eq!(turns.len(), 1);
+        turns.into_iter().next().unwrap()
+    }
 
-                let pos: Box<[f32]> = pos.iter().map(|c| c.0 as f32).collect();
+    #[test]
+    fn test_inverse() {
+        assert_eq!(
+            Turn::Side(SideTurn {
+                layers: LayerMask::new(),
+                side: Side::new(0),
+                from: Axis::new(1),
+                to: Axis::new(2),
+            })
+            .inverse(),
+            Turn::Side(SideTurn {
+                layers: LayerMask::new(),
+                side: Side::new(0),
+                from: Axis::new(2),
+                to: Axis::new(1)
+            })
+        );
+        assert_eq!(
+            Turn::Puzzle(PuzzleTurn {
+                from: Axis::new(0),
+                to: Axis::new(1),
+            })
+            .inverse(),
+            Turn::Puzzle(PuzzleTurn {
+                from: Axis::new(1),
+                to: Axis::new(0),
+            })
+        );
+    }
 
-                // project from nd to 4d
-                let pos: Box<[f32]> = if pos.len() > 4 {
-                    self.cam_nd_to_4d.project(&pos).into()
-                } else {
-                    pos
-                };
-                assert!(pos.len() <= 4);
-
-                // project from 4d to 3d
-                let (pos, depth_4d_to_3d) = if let Some(pos) = pos.as_array::<4>() {
-                    let (pos, depth) = self.cam_4d_to_3d.project(pos);
-                    // actually instead of culling each sticker,
-                    // pick a side to cull,
-                    // and cull all the stickers on that side.
-
-                    // // depth culling of (hopefully) the outer face
-                    // if depth > 2.0 {
-                    //     continue;
-                    // }
-                    (pos.into(), depth)
-                } else {
-                    (pos, 1.0)
-                };
-                assert!(pos.len() <= 3);
+    #[test]
+    fn test_notation() {
+        for s in ["R[UF]", "I[OR]", "{2}L[FU]", "{1,3}Ψ[ΓA]", "*[RO]"] {
+            assert_eq!(turn(s).to_string(), s);
+        }
+        assert_eq!(turn("{1}R[UF]"), turn("R[UF]"));
+        for s in [
+            "R", "R[U]", "R[DF]", "R[UF]]", "{0}R[UF]", "{1}*[UF]", "X[UF]",
+        ] {
+            assert!(Turn::parse_seq(s).is_err(), "{s:?}");
+        }
+        assert_eq!(Turn::parse_seq(" R[UF]  *[RU] ").unwrap().len(), 2);
+        assert!(Turn::parse_seq("R[UF] R").is_err());
+    }
 
-                // project from 3d to 2d
-                let (pos, depth_3d_to_2d) = if let Some(pos) = pos.as_array::<3>() {
-                    let (pos, depth) = self.cam_3d_to_2d.project(pos);
-                    // TODO: maybe depth culling
-                    (pos, depth)
-                } else {
-                    (*pos.as_array().unwrap(), 0.0)
-                };
+    #[test]
+    fn test_commutator_notation() {
+        let a = turn("R[UF]");
+        let b = turn("U[RF]");
+        assert_eq!(
+            Turn::parse_seq("[R[UF], U[RF]]").unwrap(),
+            vec![a.clone(), b.clone(), a.inverse(), b.inverse()]
+        );
+        assert_eq!(
+            Turn::parse_seq("[R[UF]: U[RF]]").unwrap(),
+            vec![a.clone(), b.clone(), a.inverse()]
+        );
+        assert_eq!(
+            Turn::parse_seq("(R[UF] U[RF])'").unwrap(),
+            vec![b.inverse(), a.inverse()]
+        );
+        assert_eq!(
+            Turn::parse_seq("R[UF]2 U[RF]0").unwrap(),
+            vec![a.clone(), a.clone()]
+        );
+        assert_eq!(Turn::parse_seq("[[R[UF]: U[RF]], R[UF]]").unwrap().len(), 8);
+        for s in ["[R[UF]]", "[R[UF], U[RF]", "(R[UF]", "R[UF])", "R[UF]]"] {
+            assert!(Turn::parse_seq(s).is_err(), "{s:?}");
+        }
+        assert_eq!(
+            Turn::parse_seq("R[UF] X[UF]"),
+            Err(NotationError {
+                index: 6,
+                expected: "a side"
+            })
+        );
+        assert_eq!(
+            Turn::parse_seq("(R[UF] U[FR])99999999"),
+            Err(NotationError {
+                index: 13,
+                expected: "fewer repetitions"
+            })
+        );
+        // each commutator doubles the length
+        let nested = (0..20).fold("R[UF]".to_string(), |alg, _| format!("[{alg}, U[FR]]"));
+        assert_eq!(
+            Turn::parse_seq(&nested).map_err(|err| err.expected),
+            Err("fewer turns")
+        );
+        assert_eq!(Turn::parse_seq("R[UF]50000").unwrap().len(), 50000);
+
+        // a commutator of disjoint turns does nothing
+        let shape = [3, 3, 3].map(Cut);
+        let preview = AlgPreview::new(&shape, Turn::parse_seq("[R[UF], L[UF]]").unwrap()).unwrap();
+        assert!(preview.moved_pieces.is_empty());
+        let preview = AlgPreview::new(&shape, Turn::parse_seq("R[UF]").unwrap()).unwrap();
+        assert_eq!(preview.moved_pieces.len(), 8);
+        assert!(AlgPreview::new(&shape, Turn::parse_seq("O[UF]").unwrap()).is_err());
+    }
 
-                assert!(depth_4d_to_3d.is_finite());
-                assert!(depth_3d_to_2d.is_finite());
-                // sticker_depths.push((sticker.clone(), color_side, -depth_3d_to_2d));
-                // sticker_depths.push((sticker.clone(), color_side, depth_4d_to_3d * depth_3d_to_2d));
-                sticker_depths.push((
-                    sticker.clone(),
-                    color_side,
-                    depth_4d_to_3d.signum() * depth_3d_to_2d,
-                ));
-                // sticker_depths.push((
-                //     sticker.clone(),
-                //     color_side,
-                //     depth_4d_to_3d.signum() * depth_3d_to_2d.abs(),
-                // ));
-            }
-            sticker_depths.sort_unstable_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
-            sticker_depths
-                .into_iter()
-                .map(|(sticker, color_side, _depth)| (sticker, color_side))
-                .collect::<Vec<_>>()
+    #[test]
+    fn test_cycle_structure() {
+        let shape = [3, 3, 3].map(Cut);
+        let cycle_structure = |alg: &str| {
+            StickerPermutation::new(&shape, &Turn::parse_seq(alg).unwrap())
+                .unwrap()
+                .cycle_structure()
         };
+        assert_eq!(cycle_structure(""), "nothing moves");
+        assert_eq!(
+            cycle_structure("R[UF]"),
+            "1 4-cycle of corners, 1 4-cycle of edges"
+        );
+        assert_eq!(
+            cycle_structure("R[UF]2"),
+            "2 2-cycles of corners, 2 2-cycles of edges"
+        );
+        // corner 3-cycle
+        assert_eq!(
+            cycle_structure("[[R[FU]: D[RF]], U[RF]]"),
+            "1 3-cycle of corners"
+        );
+        // sune
+        assert_eq!(
+            cycle_structure("R[UF] U[RF] R[FU] U[RF] R[UF] U[RF]2 R[FU]"),
+            "2 twisted 2-cycles of corners, 1 3-cycle of edges"
+        );
+        // twists corners in place
+        assert_eq!(
+            cycle_structure("(R[FU] D[FR] R[UF] D[RF])2"),
+            "4 twisted corners, 1 3-cycle of edges"
+        );
+        let permutation =
+            StickerPermutation::new(&shape, &Turn::parse_seq("R[UF]").unwrap()).unwrap();
+        assert_eq!(permutation.sources.len(), 20);
+        assert!(permutation.cycles().iter().all(|cycle| cycle.len() == 4));
+    }
 
-        for (sticker, color_side) in stickers {
-            let pos = &sticker.0.0;
-
-            // get the quads
-            let quads = sticker_geom_to_3d(
-                &self.cam_nd_to_4d,
-                &self.cam_4d_to_3d,
-                &self.puzzle.shape,
-                pos,
-            );
-
-            // project the quads and sort them by depth
-            let quads = {
-                let mut quads = quads
-                    .into_iter()
-                    .map(|quad| {
-                        let mut screen_corners = Vec::with_capacity(4);
-                        let mut total_depth = 0.0;
-                        for v in &quad {
-                            let ([x, y], depth) = self.cam_3d_to_2d.project(v);
-                            screen_corners
-                                .push(egui::Pos2::new(center.x + x * scale, center.y - y * scale));
-                            total_depth += depth;
-                        }
-                        (*screen_corners.as_array::<4>().unwrap(), total_depth / 4.0)
-                    })
-                    .collect::<Vec<_>>();
-                // sort by depth
-                quads.sort_unstable_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-                quads
-                    .into_iter()
-                    .map(|(screen_corners, _depth)| screen_corners)
-                    .collect::<Vec<_>>()
-            };
+    #[test]
+    fn test_order_and_parity() {
+        let analyze = |shape: &[Cut], alg: &str| {
+            let permutation =
+                StickerPermutation::new(shape, &Turn::parse_seq(alg).unwrap()).unwrap();
+            (
+                permutation.order(),
+                permutation.solved_order(),
+                permutation.orbit_parities(),
+            )
+        };
+        let shape = [3, 3, 3].map(Cut);
+        let parities = |corners, edges| {
+            vec![
+                ("corners".to_string(), corners),
+                ("edges".to_string(), edges),
+            ]
+        };
+        assert_eq!(analyze(&shape, ""), (Some(1), Some(1), vec![]));
+        assert_eq!(
+            analyze(&shape, "R[UF]"),
+            (Some(4), Some(4), parities(true, true))
+        );
+        // sexy move
+        assert_eq!(
+            analyze(&shape, "R[UF] U[RF] R[FU] U[FR]"),
+            (Some(6), Some(6), parities(false, false))
+        );
+        // sune
+        assert_eq!(
+            analyze(&shape, "R[UF] U[RF] R[FU] U[RF] R[UF] U[RF]2 R[FU]"),
+            (Some(6), Some(6), parities(false, false))
+        );
+        // the 2x3x4x5 has a few orbits of each kind
+        let shape = [2, 3, 4, 5].map(Cut);
+        let parities = |odd: &[&str], even: &[&str]| {
+            odd.iter()
+                .map(|orbit| (orbit.to_string(), true))
+                .chain(even.iter().map(|orbit| (orbit.to_string(), false)))
+                .sorted()
+                .collect_vec()
+        };
+        // a half turn, which swaps the two middle centers
+        assert_eq!(
+            analyze(&shape, "R[UF]"),
+            (
+                Some(2),
+                Some(2),
+                parities(
+                    &["2c pieces [1, 0, 3, 0]", "centers [1, 0, 1, 0]"],
+                    &[
+                        "2c pieces [1, 0, 1, 4]",
+                        "2c pieces [1, 0, 3, 2]",
+                        "2c pieces [1, 2, 1, 0]",
+                        "2c pieces [1, 2, 1, 2]",
+                        "centers [1, 0, 1, 2]",
+                        "corners",
+                        "edges [1, 0, 3, 4]",
+                        "edges [1, 2, 1, 4]",
+                        "edges [1, 2, 3, 0]",
+                        "edges [1, 2, 3, 2]",
+                    ]
+                )
+            )
+        );
+        assert_eq!(
+            analyze(&shape, "O[UF] R[UO]"),
+            (
+                Some(4),
+                Some(4),
+                parities(
+                    &[],
+                    &[
+                        "2c pieces [1, 0, 1, 4]",
+                        "2c pieces [1, 0, 3, 2]",
+                        "2c pieces [1, 2, 1, 0]",
+                        "2c pieces [1, 2, 1, 2]",
+                        "centers [1, 0, 1, 2]",
+                        "corners",
+                        "edges [1, 0, 3, 4]",
+                        "edges [1, 2, 1, 4]",
+                        "edges [1, 2, 3, 0]",
+                        "edges [1, 2, 3, 2]",
+                    ]
+                )
+            )
+        );
+    }
 
-            // sort the corners ccw
-            let quads = quads
+    #[test]
+    fn test_simplify() {
+        let shape = [3, 3, 3].map(Cut);
+        let simplify =
+            |alg: &str| Turn::format_seq(&Turn::simplify(&shape, &Turn::parse_seq(alg).unwrap()));
+        assert_eq!(simplify("R[UF] R[FU]"), "");
+        assert_eq!(simplify("R[UF]4"), "");
+        assert_eq!(simplify("R[UF]3"), "R[FU]");
+        assert_eq!(simplify("R[FU]2"), "R[UF]2");
+        assert_eq!(simplify("R[UF] L[UF] R[FU]"), "L[UF]");
+        assert_eq!(simplify("R[UF] L[UF]"), "L[UF] R[UF]");
+        assert_eq!(
+            simplify("R[UF] U[RF] R[FU] U[FR]"),
+            "R[UF] U[RF] R[FU] U[FR]"
+        );
+        assert_eq!(
+            simplify("[R[UF] L[FU]: U[RF]2] U[RF]2"),
+            "L[FU] R[UF] U[RF]2 L[UF] R[FU] U[RF]2"
+        );
+        assert_eq!(simplify("R[UF] L[FU] R[FU] U[RF]"), "L[FU] U[RF]");
+        assert_eq!(simplify("*[RU] U[RF] *[UR]"), "*[RU] U[RF] *[UR]");
+        assert_eq!(simplify("*[RU] F[RU] *[UR]"), "F[RU]");
+        assert_eq!(
+            Turn::count_moves(&shape, &Turn::parse_seq("R[UF] R[UF] *[RU] L[UF]").unwrap()),
+            2
+        );
+        // one turn at a time gives the same as simplifying all of them
+        let mut puzzle = Puzzle::new(&shape);
+        let turns = puzzle.scramble(&mut rand::rngs::StdRng::seed_from_u64(0));
+        let turns = [&turns[..], &Turn::inverse_seq(&turns[turns.len() / 2..])].concat();
+        let mut simplified = SimplifiedTurns::default();
+        for (i, turn) in turns.iter().enumerate() {
+            simplified.push(&shape, turn);
+            let moves = Turn::simplify(&shape, &turns[..=i])
                 .into_iter()
-                .map(|mut screen_corners| {
-                    let cx = screen_corners.iter().map(|p| p.x).sum::<f32>() / 4.0;
-                    let cy = screen_corners.iter().map(|p| p.y).sum::<f32>() / 4.0;
-                    screen_corners.sort_unstable_by(|a, b| {
-                        let aa = (a.y - cy).atan2(a.x - cx);
-                        let ab = (b.y - cy).atan2(b.x - cx);
-                        aa.partial_cmp(&ab).unwrap_or(std::cmp::Ordering::Equal)
-                    });
-                    screen_corners
-                })
-                .collect::<Vec<_>>();
+                .dedup()
+                .filter(|turn| matches!(turn, Turn::Side(_)))
+                .count();
+            assert_eq!(simplified.move_count(), moves);
+        }
+        assert_eq!(
+            simplified.text(&shape),
+            Turn::format_seq(&Turn::simplify(&shape, &turns))
+        );
 
-            // filter out degenerate quads
-            // TODO: do this better
-            let quads = quads
-                .into_iter()
-                .filter(|quad| {
-                    let area = {
-                        let [a, b, c, d] = *quad;
-                        let cross = |p: egui::Pos2, q: egui::Pos2, r: egui::Pos2| {
-                            (q.x - p.x) * (r.y - p.y) - (q.y - p.y) * (r.x - p.x)
-                        };
-                        (cross(a, b, c) + cross(a, c, d)).abs()
-                    };
-                    const EPSILON: f32 = 20.0;
-                    area >= EPSILON
+        // turns in non-square planes are half turns
+        let shape = [2, 3, 3].map(Cut);
+        let simplify =
+            |alg: &str| Turn::format_seq(&Turn::simplify(&shape, &Turn::parse_seq(alg).unwrap()));
+        assert_eq!(simplify("U[FR]"), "U[RF]");
+        assert_eq!(simplify("U[RF]2"), "");
+        assert_eq!(simplify("R[UF]2"), "R[UF]2");
+
+        // simplifying doesn't change what it does
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for shape in [[3, 3, 3], [2, 3, 4], [3, 3, 1]] {
+            let shape = shape.map(Cut);
+            let turns = (0..200)
+                .map(|_| {
+                    let from = rng.random_range(0..3);
+                    let to = (from + rng.random_range(1..3)) % 3;
+                    if rng.random_bool(0.2) {
+                        Turn::Puzzle(PuzzleTurn {
+                            from: Axis::new(from),
+                            to: Axis::new(to),
+                        })
+                    } else {
+                        let side = 3 - from - to;
+                        Turn::Side(SideTurn {
+                            layers: LayerMask::new(),
+                            side: Side::new(if rng.random_bool(0.5) { side } else { !side }),
+                            from: Axis::new(from),
+                            to: Axis::new(to),
+                        })
+                    }
                 })
                 .collect::<Vec<_>>();
-
-            // draw the quads
-            for quad in quads {
-                painter.add(egui::Shape::convex_polygon(
-                    quad.into(),
-                    color_side.color(),
-                    egui::Stroke::new(outline_width, outline_color),
-                ));
-            }
+            let simplified = Turn::simplify(&shape, &turns);
+            assert!(simplified.len() < turns.len());
+            assert_eq!(
+                StickerPermutation::new(&shape, &simplified).unwrap(),
+                StickerPermutation::new(&shape, &turns).unwrap(),
+            );
+            assert_eq!(Turn::simplify(&shape, &simplified), simplified);
         }
     }
-}
-impl eframe::App for App {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        ctx.request_repaint();
-        egui::CentralPanel::default()
-            .frame(egui::Frame::NONE)
-            .show(ctx, |ui| {
-                ctx.input(|i| {
-                    // handle input
-                    for event in i.events.iter() {
-                        if let egui::Event::Key {
-                            key,
-                            physical_key: _,
-                            pressed,
-                            repeat,
-                            modifiers: _,
-                        } = event
-                            && *pressed
-                            && !repeat
-                            && let Some(turn) = self.turn_builder.update(*key)
-                        {
-                            self.puzzle.turn(&turn);
-                            println!("solved: {}", self.puzzle.is_solved());
-                        }
-                    }
-                });
 
-                // draw puzzle with Camera
-                self.ui_puzzle(ui);
+    #[test]
+    fn test_solver() {
+        assert_eq!(Symmetry::rotations(&[3, 3, 3].map(Cut)).len(), 24);
+        assert_eq!(Symmetry::rotations(&[2, 2, 3].map(Cut)).len(), 8);
+        assert_eq!(Symmetry::rotations(&[2, 2, 2, 2].map(Cut)).len(), 192);
+        for shape in [vec![3, 3, 3], vec![2, 3, 2], vec![1, 2, 1, 2], vec![4]] {
+            let shape = shape.into_iter().map(Cut).collect_vec();
+            let rotations = Symmetry::rotations(&shape);
+            assert!(rotations.iter().all_unique());
+            assert_eq!(Symmetry::rotation_count(&shape), Some(rotations.len()));
+        }
+        // 8! * 2^8 / 2 rotations wouldn't fit in the table, so it doesn't try
+        assert!(Solver::new(&[Cut(1); 8]).is_err());
+        assert_eq!(Symmetry::rotation_count(&[Cut(1); 30]), None);
+
+        let shape = [2, 2, 2].map(Cut);
+        let solver = Solver::new(&shape).unwrap();
+        assert_eq!(solver.solve(&Puzzle::new(&shape)).unwrap(), vec![]);
+        let scramble = Turn::parse_seq("R[UF] U[RF] F[UR] U[FR] R[FU] F[RU] U[RF]").unwrap();
+        let mut puzzle = Puzzle::new(&shape);
+        for turn in &scramble {
+            puzzle.turn(turn).unwrap();
+        }
+        let solution = solver.solve(&puzzle).unwrap();
+        assert!(solution.len() <= scramble.len());
+        for turn in &solution {
+            puzzle.turn(turn).unwrap();
+        }
+        assert!(puzzle.is_solved());
 
-                // draw puzzle with Layout2d
-                #[cfg(false)]
-                {
-                    // we want 1 sticker of margin on each side
-                    let rect = ctx.content_rect();
-                    let scale = f32::min(
-                        rect.width() / (self.layout.width + 2) as f32,
-                        rect.height() / (self.layout.height + 2) as f32,
-                    );
+        // towards the mirrored color scheme
+        let mut puzzle = Puzzle::new(&shape);
+        for turn in &scramble {
+            puzzle.turn(turn).unwrap();
+        }
+        puzzle.mirror(Axis::new(1));
+        for turn in &solver.solve(&puzzle).unwrap() {
+            puzzle.turn(turn).unwrap();
+        }
+        assert!(puzzle.is_solved());
 
-                    let screen_of_pos = |pos: &Position| -> egui::Pos2 {
-                        let (x, y) = self.layout.mapping[pos];
-                        egui::Pos2::new(1.5 + x as f32, 1.5 + (self.layout.height - 1 - y) as f32)
-                            * scale
-                    };
-                    let pos_of_screen = |screen: egui::Pos2| -> Option<Position> {
-                        let x = (screen.x / scale - 1.5).round() as i32;
-                        let y =
-                            self.layout.height as i32 - 1 - (screen.y / scale - 1.5).round() as i32;
-                        if !(0..self.layout.width as _).contains(&x)
-                            || !(0..self.layout.height as _).contains(&y)
-                        {
-                            return None;
-                        }
-                        self.layout.inverse.get(&(x as usize, y as usize)).cloned()
-                    };
+        // half turns, and an axis with only 1 layer
+        let shape = [1, 2, 3].map(Cut);
+        let solver = Solver::new(&shape).unwrap();
+        let mut puzzle = Puzzle::new(&shape);
+        puzzle.scramble(&mut rand::rngs::StdRng::seed_from_u64(0));
+        for turn in &solver.solve(&puzzle).unwrap() {
+            puzzle.turn(turn).unwrap();
+        }
+        assert!(puzzle.is_solved());
 
-                    let hovered_pos: Option<Position> =
-                        ui.input(|i| i.pointer.hover_pos()).and_then(pos_of_screen);
-                    let hovered_piece = hovered_pos.and_then(|pos| {
-                        Piece::try_from(&self.puzzle.shape, pos.clone()).or_else(|| {
-                            Sticker::try_from(&self.puzzle.shape, pos.clone())
-                                .map(|sticker| sticker.piece(&self.puzzle.shape))
-                        })
-                    });
+        assert!(Solver::new(&[5, 5, 5].map(Cut)).is_err());
+    }
 
-                    // if we clicked, added the hovered piece to the clicked pieces
-                    if let Some(hovered_piece) = &hovered_piece
-                        && ui.input(|i| i.pointer.button_pressed(egui::PointerButton::Primary))
-                    {
-                        if self.clicked_pieces.contains(hovered_piece) {
-                            self.clicked_pieces.remove(hovered_piece);
-                        } else {
-                            self.clicked_pieces.insert(hovered_piece.clone());
-                        }
-                    }
+    #[test]
+    fn test_hint() {
+        let shape = [3, 3, 3].map(Cut);
+        let mut filter = Filter {
+            terms: vec![FilterTerm::new()],
+            format: StickerFormatBuilder::NONE,
+        };
+        filter.terms[0]
+            .must_have
+            .insert(Side::try_from_name('U').unwrap());
+        let pieces = Position::all(&shape)
+            .filter_map(|pos| Piece::try_from(&shape, pos))
+            .filter(|piece| filter.selects(&shape, piece))
+            .count();
+        assert_eq!(pieces, 9);
+        // pieces can have other sides too, and no sides at all means every piece
+        let corner = Piece::try_from(&shape, Position(vec![Coord(2), Coord(2), Coord(2)])).unwrap();
+        assert!(filter.selects(&shape, &corner));
+        assert!(FilterTerm::new().selects(&shape, &corner));
+        // but the filters that show pieces only have pieces with just those sides
+        assert!(!filter.contains(&shape, &corner));
+        assert!(!FilterTerm::new().contains(&shape, &corner));
+        let mut term = FilterTerm::new();
+        term.must_have = "RUFD".chars().flat_map(Side::try_from_name).collect();
+        assert!(term.contains(&shape, &corner));
+        filter.terms[0]
+            .cant_have
+            .insert(Side::try_from_name('F').unwrap());
+        assert!(!filter.selects(&shape, &corner));
+        term.cant_have = filter.terms[0].cant_have.clone();
+        assert!(!term.contains(&shape, &corner));
+        filter.terms[0].cant_have.clear();
+
+        let mut puzzle = Puzzle::new(&shape);
+        let running = AtomicBool::new(false);
+        assert!(Hint::new(&puzzle, &filter, &running).is_err());
+        for turn in Turn::parse_seq("R[UF] U[RF]").unwrap() {
+            puzzle.turn(&turn).unwrap();
+        }
+        let job = {
+            let (puzzle, filter) = (puzzle.clone(), filter.clone());
+            Job::spawn(move |cancelled| Hint::new(&puzzle, &filter, cancelled))
+        };
+        let hint = loop {
+            if let Some(hint) = job.get() {
+                break hint.clone().unwrap();
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        };
+        assert_eq!(hint.solved.1, 9);
+        assert_eq!(hint.turns, Turn::parse_seq("U[FR] R[FU]").unwrap());
+        assert_eq!(hint.moved_pieces.len(), 8);
+        assert_eq!(
+            Hint::new(&puzzle, &filter, &AtomicBool::new(true)).unwrap_err(),
+            "cancelled"
+        );
+        // too many turns to look through
+        let shape = [Cut(2); 8];
+        let err = Hint::new(&Puzzle::new(&shape), &filter, &running).unwrap_err();
+        assert!(err.starts_with("too big"), "{err}");
+
+        // the pieces on the L side but not the U side didn't move
+        filter.terms[0].must_have = HashSet::from_iter([Side::try_from_name('L').unwrap()]);
+        filter.terms[0].cant_have = HashSet::from_iter([Side::try_from_name('U').unwrap()]);
+        assert_eq!(
+            Hint::new(&puzzle, &filter, &running).unwrap_err(),
+            "already solved"
+        );
+    }
 
-                    // TODO: layer mask
-                    let gripped_side = match &self.turn_builder {
-                        TurnBuilder::Side { layers, side, .. } => *side,
-                        TurnBuilder::Puzzle { .. } => None,
-                    };
-                    let format_sticker = |sticker: &Sticker| -> StickerFormat {
-                        let mut ret = StickerFormatBuilder::NONE;
-                        if let Some(hovered_piece) = hovered_piece.clone()
-                            && sticker.piece(&self.puzzle.shape) == hovered_piece
-                        {
-                            ret.update(&self.hovered_format);
-                        }
-                        for clicked_piece in &self.clicked_pieces {
-                            if sticker.piece(&self.puzzle.shape) == *clicked_piece {
-                                ret.update(&self.clicked_format);
-                            }
-                        }
-                        if Some(sticker.side(&self.puzzle.shape)) == gripped_side {
-                            ret.update(&self.gripped_format);
-                        }
-                        if let Some(filter_stage) = self.filter_stage {
-                            for filter in &self.filter_sequence.0[filter_stage].0 {
-                                if filter.contains(
-                                    &self.puzzle.shape,
-                                    &sticker.piece(&self.puzzle.shape),
-                                ) {
-                                    ret.update(&filter.format);
-                                }
-                            }
-                            ret.build_or(&self.default_filter_format)
-                        } else {
-                            ret.build_or(&self.default_no_filter_format)
-                        }
-                    };
+    #[test]
+    fn test_split_polygons() {
+        let square = |corners: [[f32; 3]; 4]| Polygon::new(corners.map(na::Vector3::from).to_vec());
+        let flat = square([
+            [-1.0, -1.0, 0.0],
+            [1.0, -1.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [-1.0, 1.0, 0.0],
+        ]);
+        let upright = |x: f32, z: f32| {
+            square([
+                [x, -1.0, z - 1.0],
+                [x, 1.0, z - 1.0],
+                [x, 1.0, z + 1.0],
+                [x, -1.0, z + 1.0],
+            ])
+        };
+        assert!(flat.crosses(&upright(0.0, 0.0)));
+        // beside it, touching its edge, and standing on it
+        assert!(!flat.crosses(&upright(2.0, 0.0)));
+        assert!(!flat.crosses(&upright(1.0, 0.0)));
+        assert!(!flat.crosses(&upright(0.0, 1.0)));
+
+        let parts = flat.split(upright(0.5, 0.0).plane().unwrap()).unwrap();
+        let xs = parts
+            .iter()
+            .map(|part| {
+                assert_eq!(part.verts.len(), 4);
+                // the cut isn't outlined
+                assert_eq!(part.edges.iter().filter(|&&edge| !edge).count(), 1);
+                let (min, max) = part.bounds();
+                [min.x, max.x]
+            })
+            .sorted_by(|a, b| a[0].total_cmp(&b[0]))
+            .collect_vec();
+        assert_eq!(xs, [[-1.0, 0.5], [0.5, 1.0]]);
+
+        let split = Polygon::split_crossing(vec![
+            (flat.clone(), 'a'),
+            (upright(0.0, 0.0), 'b'),
+            (upright(3.0, 0.0), 'c'),
+        ]);
+        assert_eq!(
+            split.iter().map(|(_, data)| *data).collect::<String>(),
+            "aabbc"
+        );
+    }
 
-                    let painter = ui.painter();
-                    // TODO: pixel alignment
-                    let draw_position = |pos: &Position, color: Color32, format: &StickerFormat| {
-                        let rect = egui::Rect::from_center_size(
-                            screen_of_pos(pos),
-                            egui::Vec2::new(1.0, 1.0) * scale * format.sticker_scale,
-                        );
+    #[test]
+    fn test_camera_fov() {
+        let mut cam = Camera4dTo3d::new();
+        let (pos, depth) = cam.project(&[1.0, 0.0, 0.0, 0.5]);
+        assert!((pos[0] - 1.0 / 1.5).abs() < 1e-6);
+        assert!((depth - 1.5).abs() < 1e-6);
+        // at and past the camera, which is at w = 2
+        for w in [2.0, 2.5, 3.0] {
+            let (pos, depth) = cam.project(&[0.1, 0.0, 0.0, w]);
+            assert!(pos.iter().all(|x| x.is_finite()));
+            assert!(depth <= 0.0, "{w}");
+        }
+        cam.fov = 0.0;
+        assert_eq!(cam.project(&[1.0, 2.0, 3.0, 0.5]).0, [1.0, 2.0, 3.0]);
+
+        let mut cam = Camera3dTo2d::new();
+        assert_eq!(cam.project(&[1.0, 2.0, 3.0]), ([1.0, 2.0], 3.0));
+        cam.fov = 60.0;
+        assert_eq!(cam.project(&[1.0, 2.0, 0.0]).0, [1.0, 2.0]);
+        let ([near, _], _) = cam.project(&[1.0, 0.0, 0.5]);
+        let ([far, _], _) = cam.project(&[1.0, 0.0, -0.5]);
+        assert!(near > 1.0 && far < 1.0);
+        // points on a line through the eye land on the same spot
+        let eye = cam.eye().unwrap();
+        let further = eye + (na::Vector3::new(1.0, 2.0, 0.0) - eye) * 2.0;
+        let ([x, y], _) = cam.project(&[further.x, further.y, further.z]);
+        assert!((x - 1.0).abs() < 1e-5 && (y - 2.0).abs() < 1e-5);
+        cam.fov = 0.0;
+        assert_eq!(cam.eye(), None);
+
+        // the rows stay orthonormal
+        let mut cam = CameraNdTo4d::new(6);
+        cam.angles[1] = 1.0;
+        cam.rebuild();
+        let gram = &cam.rot * cam.rot.transpose();
+        assert!((gram - na::Matrix4::identity()).norm() < 1e-5);
+    }
 
-                        painter.rect(
-                            rect,
-                            // TODO: custom corner radius
-                            0.2 * scale * format.sticker_scale,
-                            // TODO: is unmultiplied correct
-                            Color32::from_rgba_unmultiplied(
-                                color.r(),
-                                color.g(),
-                                color.b(),
-                                (format.sticker_opacity * 255.0) as u8,
-                            ),
-                            egui::Stroke::new(format.outline_width * scale, format.outline_color),
-                            egui::StrokeKind::Inside,
-                        );
-                    };
+    #[test]
+    fn test_camera_nd_rotate() {
+        let mut cam = CameraNdTo4d::new(6);
+        let hidden = cam.hidden();
+        assert_eq!(hidden.len(), 2);
+        let visible = cam.rot.row(0).transpose();
+        let visible = na::DVector::from_column_slice(visible.as_slice());
+        assert!(hidden[0].dot(&visible).abs() < 1e-5);
+        assert!(hidden[0].dot(&hidden[1]).abs() < 1e-5);
+
+        // a quarter turn brings the hidden axis into view, pointing the other way
+        cam.rotate(&visible, &hidden[0], std::f32::consts::FRAC_PI_2);
+        let row = cam.rot.row(0).transpose();
+        assert!((na::DVector::from_column_slice(row.as_slice()) + &hidden[0]).norm() < 1e-5);
+        let gram = &cam.rot * cam.rot.transpose();
+        assert!((gram - na::Matrix4::identity()).norm() < 1e-5);
+        assert_eq!(CameraNdTo4d::new(4).hidden().len(), 0);
+        assert_eq!(CameraNdTo4d::new(4).hidden_from(1).len(), 0);
+
+        // every hidden axis can be dragged into view, not just the first two
+        let mut cam = CameraNdTo4d::new(8);
+        let hidden = cam.hidden();
+        assert_eq!(hidden.len(), 4);
+        let third = &cam.hidden_from(2)[0];
+        assert!((third - &hidden[2]).norm() < 1e-5);
+        assert!((&cam.hidden_from(5)[0] - &hidden[1]).norm() < 1e-5);
+        let visible = cam.rot.row(0).transpose();
+        let visible = na::DVector::from_column_slice(visible.as_slice());
+        cam.rotate(&visible, third, std::f32::consts::FRAC_PI_2);
+        let row = cam.rot.row(0).transpose();
+        assert!((na::DVector::from_column_slice(row.as_slice()) + third).norm() < 1e-5);
+    }
 
-                    for pos in Position::all(&self.puzzle.shape) {
-                        draw_position(&pos, self.internal_color, &self.internal_format);
-                    }
-                    for (pos, side) in &self.puzzle.stickers {
-                        draw_position(&pos.0, side.color(), &format_sticker(pos));
-                    }
+    #[test]
+    fn test_view_state() {
+        let close = |a: &ViewState, b: &ViewState| {
+            (&a.rot_nd - &b.rot_nd).norm() < 1e-4
+                && (a.rot_4d - b.rot_4d).norm() < 1e-4
+                && (a.rot_3d - b.rot_3d).norm() < 1e-4
+        };
+        for side in ['R', 'U', 'F', 'L', 'D', 'B'] {
+            let side = Side::try_from_name(side).unwrap();
+            let view = ViewState::face_on(3, side);
+            let mut dir = na::Vector3::zeros();
+            dir[side.axis().into_usize()] = if side.is_positive() { 1.0 } else { -1.0 };
+            assert!((view.rot_3d * dir - na::Vector3::z()).norm() < 1e-5);
+            // face on views are already lined up
+            assert!(close(&view, &view.snapped([true; 3])));
+        }
 
-                    // TODO: fancy text sizing
-                    let render_axis_keys = match self.turn_builder {
-                        TurnBuilder::Side { side, .. } => side.is_some(),
-                        TurnBuilder::Puzzle { .. } => true,
-                    };
-                    for (side, pos) in &self.side_positions {
-                        if render_axis_keys && !side.is_positive() {
-                            continue;
+        let default = ViewState::default(5);
+        let isometric = ViewState::isometric(5);
+        assert!(close(&default.lerp(&isometric, 0.0), &default));
+        assert!(close(&default.lerp(&isometric, 1.0), &isometric));
+        let halfway = default.lerp(&ViewState::face_on(5, Side::new(4)), 0.5);
+        assert!(
+            (&halfway.rot_nd * halfway.rot_nd.transpose() - na::DMatrix::identity(4, 4)).norm()
+                < 1e-4
+        );
+        let tilted = ViewState {
+            rot_3d: *na::Rotation3::from_euler_angles(0.1, -0.2, 0.05).matrix(),
+            ..ViewState::default(4)
+        };
+        assert!(close(
+            &tilted.snapped([true, false, false]),
+            &ViewState::default(4)
+        ));
+    }
+
+    #[test]
+    fn test_symmetry() {
+        let shape = [3, 3, 3, 3].map(Cut);
+        let mut scrambled = Puzzle::new(&shape);
+        scrambled.scramble(&mut rand::rngs::StdRng::seed_from_u64(0));
+        let turns = Turn::parse_seq("R[UF] {1,2}I[RF] D[FO] L[OU]").unwrap();
+        for from in -4..4 {
+            for to in -4..4 {
+                let symmetry = Symmetry::taking(&shape, Side::new(from), Side::new(to)).unwrap();
+                assert_eq!(symmetry.side(Side::new(from)), Side::new(to));
+                // applying a symmetry to the puzzle commutes with turning
+                let mut puzzle = Puzzle::new(&shape);
+                let mut rotated = Puzzle::new(&shape);
+                for puzzle in [&mut puzzle, &mut rotated] {
+                    puzzle.stickers = scrambled.stickers.clone();
+                }
+                let rotate = |puzzle: &mut Puzzle| {
+                    let mut new_stickers = HashMap::new();
+                    for (sticker, color) in &puzzle.stickers {
+                        let mut pos = vec![Coord(0); shape.len()];
+                        for (axis, coord) in sticker.0.0.iter().enumerate() {
+                            let image = symmetry.0[axis];
+                            pos[image.axis().into_usize()] =
+                                if image.is_positive() { *coord } else { -*coord };
                         }
-                        painter.text(
-                            screen_of_pos(&pos.0),
-                            egui::Align2::CENTER_CENTER,
-                            if render_axis_keys {
-                                side.into_axis().axis_key().to_string()
-                            } else {
-                                side.side_key().to_string()
-                            },
-                            egui::TextStyle::Monospace.resolve(&ctx.style()),
-                            Color32::LIGHT_GRAY,
-                        );
+                        new_stickers.insert(Sticker::new(&shape, pos), *color);
                     }
+                    puzzle.stickers = new_stickers;
+                };
+                for turn in &turns {
+                    puzzle.turn(turn).unwrap();
                 }
-
-                // painter.text(
-                //     egui::Pos2::new(10.0, ui.available_height() - 10.0),
-                //     egui::Align2::LEFT_BOTTOM,
-                //     format!("{:?}", self.turn_builder),
-                //     egui::TextStyle::Monospace.resolve(&ctx.style()),
-                //     Color32::LIGHT_GRAY,
-                // );
-
-                // debug seeing if stuff is aligned
-                #[cfg(false)]
-                {
-                    painter.line_segment(
-                        [egui::Pos2::ZERO, egui::Pos2::new(1000.0, 1000.0)],
-                        egui::Stroke::new(1.0, Color32::WHITE),
-                    );
-                    for i in 0..=self.layout.width.max(self.layout.height) + 2 {
-                        let c = i as f32 * scale;
-                        painter.line_segment(
-                            [egui::Pos2::new(c, 0.0), egui::Pos2::new(c, 1000.0)],
-                            egui::Stroke::new(1.0, Color32::WHITE),
-                        );
-                        painter.line_segment(
-                            [egui::Pos2::new(0.0, c), egui::Pos2::new(1000.0, c)],
-                            egui::Stroke::new(1.0, Color32::WHITE),
-                        );
-                    }
+                rotate(&mut puzzle);
+                rotate(&mut rotated);
+                for turn in &turns {
+                    rotated.turn(&symmetry.turn(turn)).unwrap();
                 }
+                assert_eq!(puzzle, rotated);
+            }
+        }
+        assert_eq!(
+            Symmetry::taking(&[2, 3, 3].map(Cut), Side::new(0), Side::new(1)),
+            None
+        );
+        assert!(Symmetry::taking(&[2, 3, 3].map(Cut), Side::new(0), Side::new(!0)).is_some());
+    }
 
-                // `Area` to draw on top of the puzzle
-                egui::Area::new("ui".into())
-                    .constrain_to(ctx.content_rect())
-                    .anchor(egui::Align2::LEFT_TOP, egui::Vec2::ZERO)
-                    // .fixed_pos(egui::Pos2::new(0.0, 0.0))
-                    .show(ui.ctx(), |ui| {
-                        // draw ui
-                        ui.horizontal(|ui| {
-                            if ui.button("scramble").clicked() {
-                                self.puzzle.scramble(&mut rand::rng());
-                            }
-                            ui.collapsing("shape", |ui| {
-                                if ui.button("build").clicked() {
-                                    self.rebuild();
-                                }
-                                ui.add(egui::Slider::new(&mut self.ui_dim, 2..=7).text("dim"));
-                                // grow/shrink ui_cuts to match ui_dim, defaulting new axes to 3
-                                self.ui_cuts.resize(self.ui_dim, 3);
-                                for i in 0..self.ui_dim {
-                                    ui.add(
-                                        egui::Slider::new(&mut self.ui_cuts[i], 1..=7)
-                                            .text(format!("axis {}", i)),
-                                    );
-                                }
-                            });
-                        });
-                    });
-            });
+    #[test]
+    fn test_shade() {
+        let white = Color32::WHITE;
+        assert_eq!(App::shade(white, &App::LIGHT), white);
+        let away = App::shade(white, &-App::LIGHT);
+        assert_eq!(
+            away,
+            Color32::from_gray((255.0 * App::AMBIENT).round() as u8)
+        );
+        let side = App::shade(white, &na::Vector3::z());
+        assert!(side.r() > away.r() && side.r() < white.r());
     }
-}
 
-fn main() -> eframe::Result {
-    // unsafe { std::env::set_var("RUST_BACKTRACE", "1") };
-    // env_logger::init();
+    #[test]
+    fn test_cell_culling() {
+        let mut app = App::new(&[3, 3, 3, 3].map(Cut));
+        let sides = |names: &str| {
+            names
+                .chars()
+                .map(|c| Side::try_from_name(c).unwrap())
+                .collect()
+        };
+        assert_eq!(app.culled_sides(), sides("O"));
+        app.cell_culling = CellCulling::FacingAway;
+        assert_eq!(app.culled_sides(), sides("O"));
+        app.cell_culling = CellCulling::None;
+        assert_eq!(app.culled_sides(), sides(""));
+        app.cell_culling = CellCulling::Selected;
+        app.shown_sides.remove(&Side::try_from_name('U').unwrap());
+        assert_eq!(app.culled_sides(), sides("U"));
+
+        // turned halfway between R and O, the camera sees both from outside
+        let (s, c) = std::f32::consts::FRAC_PI_4.sin_cos();
+        app.cam_4d_to_3d.rot[(0, 0)] = c;
+        app.cam_4d_to_3d.rot[(0, 3)] = -s;
+        app.cam_4d_to_3d.rot[(3, 0)] = s;
+        app.cam_4d_to_3d.rot[(3, 3)] = c;
+        app.cell_culling = CellCulling::FacingAway;
+        assert_eq!(app.culled_sides(), sides("RO"));
+        app.cell_culling = CellCulling::Nearest;
+        assert_eq!(app.culled_sides().len(), 1);
+
+        assert_eq!(App::new(&[3, 3, 3].map(Cut)).culled_sides(), sides(""));
+    }
 
-    // let mut app = App::new(&[3, 3, 4, 5, 6, 7, 8].map(Cut));
-    // app.puzzle.scramble(&mut rand::rng());
-    // // app.render_png("render.png");
-    // std::hint::black_box(app);
-    // panic!();
+    #[test]
+    fn test_rotate_view() {
+        let shape = [3, 3, 3].map(Cut);
+        let symmetry = PuzzleTurn {
+            from: Axis::new(0),
+            to: Axis::new(1),
+        }
+        .symmetry(&shape);
+        assert_eq!(symmetry.compose(&symmetry.inverse()), Symmetry::identity(3));
+        assert_eq!(
+            symmetry.matrix() * symmetry.inverse().matrix(),
+            na::DMatrix::identity(3, 3)
+        );
 
-    // let app = App::new(&[2, 3, 4].map(Cut));
-    let app = App::new(&[3, 3, 4, 4].map(Cut));
-    let native_options = eframe::NativeOptions::default();
-    eframe::run_native(
-        "rectangle",
-        native_options,
-        Box::new(|_cc| Ok(Box::new(app))),
-    )
-}
+        let mut app = App::new(&shape);
+        app.rotate_view = true;
+        app.turn_on_screen(&turn("*[RU]"), 0.0).unwrap();
+        assert_eq!(app.puzzle, Puzzle::new(&shape));
+        assert!(app.history.is_empty());
+        // the side that was on R is on U now
+        let r = Side::try_from_name('R').unwrap();
+        let u = Side::try_from_name('U').unwrap();
+        assert_eq!(app.orientation.side(r), u);
+        app.turn_on_screen(&turn("U[FR]"), 0.0).unwrap();
+        assert_eq!(
+            app.history,
+            vec![app.orientation.inverse().turn(&turn("U[FR]"))]
+        );
+        let Turn::Side(SideTurn { side, .. }) = &app.history[0] else {
+            panic!("expected a side turn");
+        };
+        assert_eq!(*side, r);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        // turning the stickers instead
+        let mut app = App::new(&shape);
+        app.turn_on_screen(&turn("*[RU]"), 0.0).unwrap();
+        assert_ne!(app.puzzle, Puzzle::new(&shape));
+        assert_eq!(app.orientation, Symmetry::identity(3));
+    }
 
     #[test]
-    fn test_inverse() {
+    fn test_macro() {
+        let m = Macro::new("sune".into(), Turn::parse_seq("U[RF] R[UF] *[UR]").unwrap());
+        assert_eq!(m.grip, Side::new(1));
+        assert_eq!(Macro::from_line(&m.to_line()), Some(m.clone()));
         assert_eq!(
-            Turn::Side(SideTurn {
-                layers: LayerMask::new(),
-                side: Side::new(0),
-                from: Axis::new(1),
-                to: Axis::new(2),
-            })
-            .inverse(),
-            Turn::Side(SideTurn {
-                layers: LayerMask::new(),
-                side: Side::new(0),
-                from: Axis::new(2),
-                to: Axis::new(1)
-            })
+            m.turns_at(&[3, 3, 3].map(Cut), Side::new(2)).unwrap(),
+            Turn::parse_seq("F[UR] R[UF] *[FR]").unwrap()
         );
+        assert_eq!(m.turns_at(&[3, 3].map(Cut), Side::new(0)), None);
+    }
+
+    #[test]
+    fn test_settings() {
+        let mut app = App::new(&[2, 3, 4].map(Cut));
+        app.cam_3d_to_2d.fov = 45.0;
+        app.cell_culling = CellCulling::FacingAway;
+        app.hovered_format.sticker_scale = Some(0.5);
+        app.color_scheme = ColorScheme::builtin().swap_remove(1);
+        app.keybinds.puzzle = 'z';
+        let settings = app.settings();
         assert_eq!(
-            Turn::Puzzle(PuzzleTurn {
-                from: Axis::new(0),
-                to: Axis::new(1),
-            })
-            .inverse(),
-            Turn::Puzzle(PuzzleTurn {
-                from: Axis::new(1),
-                to: Axis::new(0),
-            })
+            App::settings_shape(&settings),
+            Some(vec![Cut(2), Cut(3), Cut(4)])
+        );
+
+        let mut loaded = App::new(&[3, 3].map(Cut));
+        loaded.apply_settings(&settings);
+        assert_eq!(
+            loaded.settings().lines().skip(1).join("\n"),
+            settings.lines().skip(1).join("\n")
+        );
+        assert_eq!(loaded.cell_culling, CellCulling::FacingAway);
+
+        // bad lines are skipped, and so are keybinds that clash
+        loaded.apply_settings("fov_3d = 200\nnonsense\nsticker_size = 0.5\nkey_puzzle = f");
+        assert_eq!(loaded.cam_3d_to_2d.fov, 45.0);
+        assert_eq!(loaded.sticker_size, 0.5);
+        assert_eq!(loaded.keybinds, Keybinds::new());
+        for keys in ["keys_axes = KJLIUOP;['", "keys_axes = kjliuop;[é"] {
+            loaded.apply_settings(keys);
+            assert_eq!(loaded.keybinds, Keybinds::new(), "{keys}");
+        }
+        assert!(Keybinds::new().is_valid());
+        assert_eq!(Keybinds::char_of(egui::Key::Quote), Some('\''));
+        assert_eq!(App::settings_shape("shape = 3x0"), None);
+        assert_eq!(App::settings_shape("shape = 1000x1000x1000"), None);
+
+        let format = StickerFormatBuilder::from_setting("#ff0000ff 0.1 - 1").unwrap();
+        assert_eq!(format.outline_color, Some(Color32::RED));
+        assert_eq!(format.sticker_scale, None);
+        assert_eq!(
+            StickerFormatBuilder::from_setting(&format.to_setting()).map(|f| f.to_setting()),
+            Some(format.to_setting())
+        );
+        assert!(StickerFormat::from_setting("#ff0000ff 0.1 - 1").is_none());
+    }
+
+    #[test]
+    fn test_high_dimensions() {
+        let sides = (0..Side::MAX_AXES as i16)
+            .flat_map(|i| [Side(i), !Side(i)])
+            .collect::<Vec<_>>();
+        assert!(sides.iter().map(|side| side.name()).all_unique());
+        assert!(sides.iter().map(|side| side.color()).all_unique());
+        for side in &sides {
+            assert_eq!(Side::try_from_name(side.name()), Some(*side));
+        }
+        assert_eq!(Side(10).name(), 'Б');
+        assert_eq!(Side(!15).name(), 'Ы');
+        // only the names that are used are sides
+        for name in ['В', 'О', 'Р', 'Г', '中', '😀'] {
+            assert_eq!(Side::try_from_name(name), None, "{name}");
+        }
+        assert!(Cut::parse_shape(&["1"; 12].join("x")).is_some());
+        assert!(Cut::parse_shape(&vec!["1"; Side::MAX_AXES + 1].join("x")).is_none());
+        let keybinds = Keybinds::new();
+        assert_eq!(keybinds.side_key(Side(12)).as_deref(), Some("⇧r"));
+        assert_eq!(keybinds.axis_key(Axis::new(15)).as_deref(), Some("⇧o"));
+        for side in (0..Side::MAX_AXES as i16).flat_map(|i| [Side::new(i), !Side::new(i)]) {
+            assert!(keybinds.side_key(side).is_some(), "{side:?}");
+        }
+
+        let mut shape = vec![Cut(1); 12];
+        shape[0] = Cut(2);
+        shape[1] = Cut(2);
+        shape[11] = Cut(2);
+        let mut app = App::new(&shape);
+        let high = turn(&format!("{{2}}{}[RU]", Side(11).name()));
+        app.turn(&high).unwrap();
+        assert!(!app.puzzle.is_solved());
+        assert_eq!(Turn::parse_seq(&high.to_string()), Ok(vec![high]));
+
+        // the 11th axis is the first one past the key lists
+        let mut builder = TurnBuilder::new(&shape);
+        builder.update(egui::Key::F, true, &keybinds);
+        builder.update(egui::Key::K, false, &keybinds);
+        let built = builder.update(egui::Key::J, false, &keybinds);
+        assert_eq!(built, Some(turn(&format!("{}[RU]", Side(10).name()))));
+        app.turn(&built.unwrap()).unwrap();
+        assert!(!app.puzzle.is_solved());
+        // and there's no 11th axis to pick on a 10 dimensional puzzle
+        let mut builder = TurnBuilder::new(&shape[..10]);
+        let status = builder.status();
+        builder.update(egui::Key::F, true, &keybinds);
+        assert_eq!(builder.status(), status);
+
+        let mut puzzle = Puzzle::new(&[25, 2, 2].map(Cut));
+        puzzle.turn(&turn("{13,25}R[UF]")).unwrap();
+        assert!(!puzzle.is_solved());
+        let mut layers = LayerMask::new();
+        layers.set(20, true);
+        layers.set(20, false);
+        assert_eq!(layers, LayerMask::new());
+    }
+
+    #[test]
+    fn test_saved_state() {
+        let mut app = App::new(&[3, 3, 2, 4].map(Cut));
+        app.scramble = app
+            .puzzle
+            .scramble(&mut rand::rngs::StdRng::seed_from_u64(0));
+        app.history = Turn::parse_seq("R[UF] U[FR]").unwrap();
+        for turn in app.history.clone() {
+            app.puzzle.turn(&turn).unwrap();
+        }
+        app.update_timer(1.0);
+        app.orientation = Symmetry::rotations(&app.puzzle.shape).pop().unwrap();
+        let text = app.saved_state(3.5).to_text();
+
+        let big = text.replace("shape = 3x3x2x4", "shape = 1000x1000x1000");
+        assert_ne!(big, text);
+        assert_eq!(SavedState::parse(&big).err(), Some("bad shape".into()));
+        let state = SavedState::parse(&text).unwrap();
+        assert_eq!(state.puzzle, app.puzzle);
+        assert_eq!(state.timer, (2.5, true, false));
+        assert_eq!(state.to_text(), text);
+
+        let mut loaded = App::new(&[3, 3].map(Cut));
+        loaded.restore(state, 10.0);
+        assert_eq!(loaded.saved_state(10.0).to_text(), text);
+
+        // a broken save is rejected rather than half loaded
+        assert!(SavedState::parse(&text.replace("stickers = ", "stickers = R")).is_err());
+        assert!(SavedState::parse(&text.replace("timer = 2.5", "timer = nan")).is_err());
+        assert!(SavedState::parse("").is_err());
+    }
+
+    #[test]
+    fn test_color_scheme() {
+        for scheme in ColorScheme::builtin() {
+            assert_eq!(ColorScheme::from_line(&scheme.to_line()), Some(scheme));
+        }
+        let scheme = ColorScheme::from_line("mine\tR=#00ff00ff Ω=#0000ff80").unwrap();
+        assert_eq!(scheme.color(Side::new(0)), Color32::GREEN);
+        assert_eq!(scheme.color(Side::new(1)), Side::new(1).color());
+        assert_eq!(
+            scheme.color(!Side::new(9)),
+            Color32::from_rgba_unmultiplied(0, 0, 255, 128)
         );
+        assert_eq!(ColorScheme::from_line("bad\tR=#00ff00ff X=#000000ff"), None);
+        assert_eq!(ColorScheme::from_line("no colors"), None);
+
+        // the palettes only go up to 4d
+        for scheme in ColorScheme::builtin() {
+            let colors = (0..10)
+                .flat_map(|axis| [Side::new(axis), !Side::new(axis)])
+                .map(|side| scheme.color(side))
+                .collect_vec();
+            assert!(colors.iter().all_unique(), "{}", scheme.name);
+            if scheme.palette_axes() > 0 {
+                assert_eq!(scheme.palette_axes(), 4);
+                assert_eq!(scheme.color(Side::new(4)), Side::new(4).generated_color());
+            }
+        }
     }
 
     #[test]
@@ -2216,6 +8141,420 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_turn_side_layers() {
+        let shape = [3, 3, 3].map(Cut);
+        let mut slices = Puzzle::new(&shape);
+        for turn in Turn::parse_seq("R[UF] {2}R[UF] L[UF]").unwrap() {
+            slices.turn(&turn).unwrap();
+        }
+        let mut rotated = Puzzle::new(&shape);
+        rotated.turn(&turn("*[UF]")).unwrap();
+        assert_eq!(slices, rotated);
+
+        let mut puzzle = Puzzle::new(&shape);
+        puzzle.turn(&turn("{1,2,3}R[UF]")).unwrap();
+        assert_eq!(puzzle, rotated);
+        assert_eq!(Cut(3).slab(Coord(3)), 1);
+        assert_eq!(Cut(3).slab(Coord(0)), 2);
+        assert_eq!(Cut(3).slab(Coord(-3)), 3);
+    }
+
+    #[test]
+    fn test_turn_builder_status() {
+        let mut builder = TurnBuilder::new(&[3, 3, 3].map(Cut));
+        let keybinds = Keybinds::new();
+        assert_eq!(builder.status(), "{1}, pick a side");
+        builder.update(egui::Key::Num2, false, &keybinds);
+        builder.update(egui::Key::F, false, &keybinds);
+        builder.update(egui::Key::J, false, &keybinds);
+        assert_eq!(builder.status(), "{1,2}R[U, pick an axis to turn to");
+        assert_eq!(
+            builder.update(egui::Key::L, false, &keybinds),
+            Some(turn("{1,2}R[UF]"))
+        );
+        assert_eq!(builder.status(), "{1,2}R[, pick an axis to turn from");
+        builder.update(egui::Key::X, false, &keybinds);
+        builder.update(egui::Key::K, false, &keybinds);
+        assert_eq!(
+            builder.status(),
+            "*[R, turning the whole puzzle, pick an axis to turn to"
+        );
+    }
+
+    #[test]
+    fn test_layer_entry() {
+        let mut builder = TurnBuilder::new(&[12, 3, 3].map(Cut));
+        let keybinds = Keybinds::new();
+        for key in [
+            egui::Key::F,
+            egui::Key::Num0,
+            egui::Key::Num1,
+            egui::Key::Num3,
+        ] {
+            builder.update(key, false, &keybinds);
+        }
+        // R only has 12 layers, so enter doesn't finish
+        assert_eq!(builder.status(), "{1}, typing layers 13, but R only has 12");
+        builder.update(egui::Key::Enter, false, &keybinds);
+        assert_eq!(builder.status(), "{1}, typing layers 13, but R only has 12");
+        builder.update(egui::Key::Minus, false, &keybinds);
+        builder.update(egui::Key::Enter, false, &keybinds);
+        assert_eq!(
+            builder.status(),
+            "{1}, typing layers 13-, which should be like 1-3,10"
+        );
+        builder.update(egui::Key::Backspace, false, &keybinds);
+        builder.update(egui::Key::Backspace, false, &keybinds);
+        builder.update(egui::Key::Backspace, false, &keybinds);
+        assert_eq!(
+            builder.status(),
+            "{1}, typing layers , press enter to finish"
+        );
+        builder.update(egui::Key::Enter, false, &keybinds);
+        assert_eq!(builder.status(), "{1}R[, pick an axis to turn from");
+        for key in [
+            egui::Key::Num0,
+            egui::Key::Num1,
+            egui::Key::Num0,
+            egui::Key::Minus,
+            egui::Key::Num1,
+            egui::Key::Num2,
+            egui::Key::Enter,
+            egui::Key::J,
+        ] {
+            builder.update(key, false, &keybinds);
+        }
+        assert_eq!(
+            builder.update(egui::Key::L, false, &keybinds),
+            Some(turn("{10,11,12}R[UF]"))
+        );
+
+        // U only has 3 layers
+        builder.update(egui::Key::E, false, &keybinds);
+        builder.update(egui::Key::Num4, false, &keybinds);
+        assert_eq!(builder.status(), "{1}U[, pick an axis to turn from");
+        // layers picked before the side are trimmed to it
+        builder.update(egui::Key::Escape, false, &keybinds);
+        for key in [
+            egui::Key::Num0,
+            egui::Key::Num2,
+            egui::Key::Minus,
+            egui::Key::Num1,
+            egui::Key::Num2,
+            egui::Key::Enter,
+            egui::Key::E,
+        ] {
+            builder.update(key, false, &keybinds);
+        }
+        assert_eq!(builder.status(), "{2,3}U[, pick an axis to turn from");
+        builder.update(egui::Key::Escape, false, &keybinds);
+        for key in [
+            egui::Key::Num0,
+            egui::Key::Num1,
+            egui::Key::Num0,
+            egui::Key::Enter,
+            egui::Key::E,
+        ] {
+            builder.update(key, false, &keybinds);
+        }
+        assert_eq!(builder.status(), "{1}U[, pick an axis to turn from");
+        let mut puzzle = Puzzle::new(&[12, 3, 3].map(Cut));
+        assert!(matches!(
+            puzzle.turn(&turn("{4}U[RF]")),
+            Err(TurnError::LayerOutOfRange {
+                layer: 4,
+                layers: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn test_turn_errors() {
+        let mut puzzle = Puzzle::new(&[3, 3, 3].map(Cut));
+        let err = puzzle.turn(&turn("O[UF]")).unwrap_err();
+        assert_eq!(err.to_string(), "O isn't a side of a 3d puzzle");
+        let err = puzzle.turn(&turn("R[UO]")).unwrap_err();
+        assert_eq!(err.to_string(), "O isn't an axis of a 3d puzzle");
+        let err = puzzle.turn(&turn("*[RR]")).unwrap_err();
+        assert_eq!(err.to_string(), "the axes don't make a plane to turn in");
+        assert!(puzzle.is_solved());
+
+        let mut app = App::new(&[3, 3, 3].map(Cut));
+        app.resume = Some(app.saved_state(0.0));
+        assert!(matches!(
+            app.turn_on_screen(&turn("R[UF]"), 0.0),
+            Err(TurnError::Disallowed(_))
+        ));
+        // nothing else changes the puzzle before picking either
+        assert!(matches!(
+            app.turn(&turn("R[UF]")),
+            Err(TurnError::Disallowed(_))
+        ));
+        let state = app.puzzle.to_state_string();
+        let sticker = app.puzzle.stickers.keys().next().unwrap().clone();
+        app.paint(&sticker, false);
+        assert!(app.mirror(Axis::new(0)).is_err());
+        assert!(app.forget_turns().is_err());
+        assert!(app.load_state(&state).is_err());
+        assert!(app.puzzle.is_solved() && app.history.is_empty());
+        app.resume = None;
+        app.turn_on_screen(&turn("R[UF]"), 0.0).unwrap();
+    }
+
+    #[test]
+    fn test_mirror() {
+        let shape = [3, 3, 4].map(Cut);
+        let turns = Turn::parse_seq("R[UF] {1,2}F[RU] U[FR] *[RF]").unwrap();
+        for axis in (0..3).map(Axis::from_usize) {
+            let symmetry = Symmetry::mirror(3, axis);
+            let mut a = Puzzle::new(&shape);
+            for turn in &turns {
+                a.turn(turn).unwrap();
+            }
+            a.mirror(axis);
+            let mut b = Puzzle::new(&shape);
+            b.mirror(axis);
+            for turn in &turns {
+                b.turn(&symmetry.turn(turn)).unwrap();
+            }
+            assert_eq!(a, b);
+        }
+        assert_eq!(
+            Symmetry::mirror(3, Axis::new(1)).turn(&turn("R[UF]")),
+            turn("R[FU]")
+        );
+
+        let m = Macro::new("sexy".into(), turns);
+        assert_eq!(m.mirrored(Axis::new(0)).grip, !Side(0));
+        assert_eq!(m.mirrored(Axis::new(0)).mirrored(Axis::new(0)), m);
+
+        // the app forgets turns that don't lead to the reflection
+        let mut app = App::new(&shape);
+        app.turn(&turn("R[UF]")).unwrap();
+        app.recording = Some(Vec::new());
+        assert!(matches!(
+            app.mirror(Axis::new(0)),
+            Err(TurnError::Disallowed(_))
+        ));
+        app.recording = None;
+        app.mirror(Axis::new(0)).unwrap();
+        assert!(app.history.is_empty());
+        assert_eq!(app.simplified.move_count(), 0);
+        assert!(!app.puzzle.is_solved());
+    }
+
+    #[test]
+    fn test_state_string() {
+        for shape in [
+            vec![1],
+            vec![1, 1, 1],
+            vec![1, 2, 3],
+            vec![3, 3, 3],
+            vec![2, 1, 4, 3],
+        ] {
+            let shape = shape.into_iter().map(Cut).collect_vec();
+            let stickers = Position::all(&shape)
+                .filter(|pos| pos.is_sticker(&shape))
+                .count();
+            assert_eq!(Cut::sticker_count(&shape), stickers, "{shape:?}");
+        }
+        // too big to build, without hanging on the way
+        assert_eq!(
+            Puzzle::from_state_string("1000x1000x1000 R"),
+            Err("bad shape 1000x1000x1000".into())
+        );
+        assert!(Cut::is_buildable(&[98, 98, 98].map(Cut)));
+        assert!(!Cut::is_buildable(&[99, 99, 99].map(Cut)));
+
+        let mut puzzle = Puzzle::new(&[2, 3, 4].map(Cut));
+        puzzle.scramble(&mut rand::rngs::StdRng::seed_from_u64(0));
+        let state = puzzle.to_state_string();
+        assert!(state.starts_with("2x3x4 "));
+        assert_eq!(Puzzle::from_state_string(&state), Ok(puzzle.clone()));
+        // wrapped when pasted
+        let (shape, stickers) = state.split_once(' ').unwrap();
+        let wrapped = stickers
+            .chars()
+            .chunks(10)
+            .into_iter()
+            .map(String::from_iter)
+            .join("\n");
+        assert_eq!(
+            Puzzle::from_state_string(&format!("  {shape}\n{wrapped}\n")),
+            Ok(puzzle.clone())
+        );
+        assert_eq!(
+            Puzzle::from_state_string(&format!("{shape} {}", &stickers[1..])),
+            Err(format!(
+                "expected {} stickers, not {}",
+                stickers.len(),
+                stickers.len() - 1
+            ))
+        );
+        assert_eq!(
+            Puzzle::from_state_string(&format!("{shape} O{}", &stickers[1..])),
+            Err("the stickers aren't sides of a 3d puzzle".into())
+        );
+        assert!(Puzzle::from_state_string("2x0 RRRR").is_err());
+        assert!(Puzzle::from_state_string("").is_err());
+
+        let mut app = App::new(&[3, 3, 3].map(Cut));
+        app.turn(&turn("R[UF]")).unwrap();
+        app.load_state(&state).unwrap();
+        assert_eq!(app.puzzle, puzzle);
+        assert_eq!(app.ui_cuts, [2, 3, 4]);
+        assert!(app.history.is_empty());
+    }
+
+    #[test]
+    fn test_problems() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for shape in [
+            vec![3, 3, 3],
+            vec![2, 2, 2],
+            vec![2, 3, 4],
+            vec![4, 4, 4],
+            vec![3, 3, 3, 3],
+            vec![2, 2, 3, 3],
+        ] {
+            let mut puzzle = Puzzle::new(&shape.iter().map(|&n| Cut(n)).collect_vec());
+            puzzle.scramble(&mut rng);
+            assert_eq!(puzzle.problems(), Vec::<String>::new(), "{shape:?}");
+        }
+
+        let shape = [3, 3, 3].map(Cut);
+        let sticker = |coords: [i16; 3]| Sticker::new(&shape, coords.map(Coord).to_vec());
+        let swapped = |a: Sticker, b: Sticker| {
+            let mut puzzle = Puzzle::new(&shape);
+            let (color_a, color_b) = (puzzle.stickers[&a], puzzle.stickers[&b]);
+            puzzle.stickers.insert(a, color_b);
+            puzzle.stickers.insert(b, color_a);
+            puzzle.problems()
+        };
+        // flipping an edge
+        assert_eq!(
+            swapped(sticker([3, 2, 0]), sticker([2, 3, 0])),
+            ["the pieces are flipped in a way turning can't fix, like one of the edges"]
+        );
+        // swapping two edges
+        let mut puzzle = Puzzle::new(&shape);
+        for (a, b) in [([3, 2, 0], [-3, 2, 0]), ([2, 3, 0], [-2, 3, 0])] {
+            let (a, b) = (sticker(a), sticker(b));
+            let (color_a, color_b) = (puzzle.stickers[&a], puzzle.stickers[&b]);
+            puzzle.stickers.insert(a, color_b);
+            puzzle.stickers.insert(b, color_a);
+        }
+        assert_eq!(
+            puzzle.problems(),
+            ["the pieces are swapped in a way turning can't fix, like two of the corners"]
+        );
+        // swapping two colors of a corner makes its mirror image
+        assert_eq!(
+            swapped(sticker([3, 2, 2]), sticker([2, 3, 2])),
+            ["the RUF piece is a mirror image"]
+        );
+        // the mirrored color scheme can be solved too, but not a mix of both
+        for shape in [[3, 3, 3], [2, 3, 4]] {
+            let shape = shape.map(Cut);
+            for axis in (0..3).map(Axis::from_usize) {
+                let mut puzzle = Puzzle::new(&shape);
+                puzzle.mirror(axis);
+                assert!(puzzle.is_solved());
+                assert_eq!(
+                    puzzle.problems(),
+                    Vec::<String>::new(),
+                    "{shape:?} {axis:?}"
+                );
+                puzzle.scramble(&mut rng);
+                assert_eq!(
+                    puzzle.problems(),
+                    Vec::<String>::new(),
+                    "{shape:?} {axis:?}"
+                );
+            }
+        }
+        let mut puzzle = Puzzle::new(&shape);
+        puzzle.mirror(Axis::new(0));
+        let (a, b) = (sticker([3, 2, 2]), sticker([2, 3, 2]));
+        let (color_a, color_b) = (puzzle.stickers[&a], puzzle.stickers[&b]);
+        puzzle.stickers.insert(a, color_b);
+        puzzle.stickers.insert(b, color_a);
+        assert_eq!(puzzle.problems(), ["the RUF piece is a mirror image"]);
+        // twisting a corner
+        let mut puzzle = Puzzle::new(&shape);
+        let corner = [[3, 2, 2], [2, 3, 2], [2, 2, 3]].map(sticker);
+        let colors = corner.clone().map(|sticker| puzzle.stickers[&sticker]);
+        for i in 0..3 {
+            puzzle
+                .stickers
+                .insert(corner[i].clone(), colors[(i + 1) % 3]);
+        }
+        assert_eq!(
+            puzzle.problems(),
+            ["a corner is twisted, which turning can't fix"]
+        );
+        // painting a sticker the wrong color
+        let mut puzzle = Puzzle::new(&shape);
+        puzzle.stickers.insert(sticker([3, 0, 0]), Side(1));
+        assert_eq!(
+            puzzle.problems(),
+            ["R has 8 stickers, not 9", "U has 10 stickers, not 9"]
+        );
+
+        let mut app = App::new(&shape);
+        app.turn(&turn("R[UF]")).unwrap();
+        app.painting = true;
+        assert!(matches!(
+            app.turn(&turn("R[UF]")),
+            Err(TurnError::Disallowed(_))
+        ));
+        app.painting = false;
+        app.puzzle = Puzzle::new(&shape);
+        app.painting = true;
+        // cycling goes R, L, U, ...
+        app.paint(&sticker([3, 0, 0]), false);
+        assert_eq!(app.puzzle.stickers[&sticker([3, 0, 0])], !Side(0));
+        assert!(app.history.is_empty());
+        app.paint(&sticker([3, 0, 0]), true);
+        assert!(app.puzzle.is_solved());
+        app.paint_side = Some(Side(1));
+        app.paint(&sticker([3, 0, 0]), false);
+        assert_eq!(app.puzzle.stickers[&sticker([3, 0, 0])], Side(1));
+    }
+
+    #[test]
+    fn test_grip_format() {
+        let mut app = App::new(&[3, 3, 3].map(Cut));
+        let gripped = |app: &App| {
+            app.puzzle
+                .stickers
+                .keys()
+                .map(|sticker| sticker.piece(&app.puzzle.shape))
+                .filter(|piece| app.grip_format(piece).is_some())
+                .unique()
+                .count()
+        };
+        assert_eq!(gripped(&app), 0);
+        app.turn_builder.update(egui::Key::F, false, &app.keybinds);
+        assert_eq!(gripped(&app), 9);
+        app.turn_builder
+            .update(egui::Key::Num2, false, &app.keybinds);
+        assert_eq!(gripped(&app), 17);
+        app.turn_builder
+            .update(egui::Key::Num1, false, &app.keybinds);
+        assert_eq!(gripped(&app), 8);
+
+        // just the middle slab, and after {2}R[U, only F's center is a candidate
+        app.turn_builder.update(egui::Key::J, false, &app.keybinds);
+        let f_center = &app.side_positions[&Side::new(2)];
+        assert!(std::ptr::eq(
+            app.grip_format(f_center).unwrap(),
+            &app.grip_axis_format
+        ));
+        assert_eq!(gripped(&app), 8);
+    }
+
     #[test]
     fn test_turn_puzzle_333() {
         let puzzle = Puzzle::new(&[3, 3, 3].map(Cut));